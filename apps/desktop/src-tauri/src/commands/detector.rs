@@ -3,6 +3,8 @@
 use std::path::Path;
 use serde::{Deserialize, Serialize};
 
+use super::docker::{detect_runtime, RuntimeInfo};
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DetectionResult {
     pub has_docker: bool,
@@ -13,6 +15,8 @@ pub struct DetectionResult {
     pub has_docker_compose: bool,
     pub package_scripts: Vec<String>,
     pub detected_type: String,
+    /// Docker or Podman, if one is available for the container profile.
+    pub container_runtime: Option<RuntimeInfo>,
 }
 
 #[tauri::command]
@@ -22,7 +26,7 @@ pub async fn detect_project(path: String) -> Result<DetectionResult, String> {
         return Err("Path does not exist".into());
     }
 
-    let has_docker = dir.join("Dockerfile").exists();
+    let has_docker = dir.join("Dockerfile").exists() || dir.join("Containerfile").exists();
     let has_docker_compose = dir.join("docker-compose.yml").exists() || dir.join("docker-compose.yaml").exists();
     let has_git = dir.join(".git").exists();
     let has_node = dir.join("package.json").exists();
@@ -59,5 +63,6 @@ pub async fn detect_project(path: String) -> Result<DetectionResult, String> {
         has_docker_compose,
         package_scripts,
         detected_type,
+        container_runtime: detect_runtime().await,
     })
 }
@@ -0,0 +1,561 @@
+// ============================================================
+// DevFlow Studio — Docker Execution Profile
+// Builds the `docker run` argv for a node: resource limits,
+// mounts, network, user mapping, pull policy and a
+// deterministic container name so a cancelled or timed-out
+// node can be removed with `docker rm -f`. Podman (including
+// rootless) is supported as a drop-in runtime, probed on first use.
+// ============================================================
+
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use super::executor::{env_allowed, Program};
+use super::output::sanitize_file_component;
+use super::secure_storage::SecretValue;
+
+/// Image used when a node doesn't specify one.
+pub const DEFAULT_IMAGE: &str = "ubuntu:22.04";
+
+/// How long a runtime probe may take before the runtime is considered absent.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Configuration for the Docker execution profile.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct DockerConfig {
+    /// Container engine; when unset Docker is preferred, then Podman.
+    pub runtime: Option<ContainerRuntime>,
+    pub image: Option<String>,
+    pub cpu_limit: Option<String>,
+    pub mem_limit: Option<String>,
+    /// Node env vars forwarded into the container. Entries are exact names or
+    /// `PREFIX_*` patterns. When unset, everything except `DOCKER_*` (which
+    /// configures the host CLI) is forwarded.
+    pub env_allowlist: Option<Vec<String>>,
+    /// Extra bind mounts besides the project at `/workspace`.
+    #[serde(default)]
+    pub volumes: Vec<VolumeMount>,
+    /// `--network` mode: `bridge`, `host`, `none` or a named network.
+    pub network: Option<String>,
+    /// `--user` for the container. `"host"` maps to the host UID:GID so files
+    /// written to the project aren't root-owned; unset keeps the image default.
+    pub user: Option<String>,
+    pub pull: Option<PullPolicy>,
+    pub entrypoint: Option<String>,
+    /// `--shm-size`, e.g. `1g` for browsers and ML workloads.
+    pub shm_size: Option<String>,
+    /// SELinux relabeling of bind mounts. Defaults to `private` (`:Z`) on
+    /// hosts with SELinux enforcing; use `shared` (`:z`) when parallel nodes
+    /// mount the same directory.
+    pub selinux_label: Option<SelinuxLabel>,
+}
+
+/// Container engine the profile drives. Both accept the same `run` flags.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerRuntime {
+    Docker,
+    Podman,
+}
+
+impl ContainerRuntime {
+    pub fn binary(self) -> &'static str {
+        match self {
+            ContainerRuntime::Docker => "docker",
+            ContainerRuntime::Podman => "podman",
+        }
+    }
+}
+
+/// A container runtime found on this host.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RuntimeInfo {
+    pub runtime: ContainerRuntime,
+    pub version: String,
+    /// Running without root (rootless Podman or rootless Docker).
+    pub rootless: bool,
+    /// SELinux is enforcing, so bind mounts need relabeling.
+    pub selinux: bool,
+}
+
+/// SELinux label applied to bind mounts.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SelinuxLabel {
+    /// `:z`, usable by every container.
+    Shared,
+    /// `:Z`, usable only by this container.
+    Private,
+    /// No relabeling.
+    None,
+}
+
+impl SelinuxLabel {
+    fn mount_option(self) -> Option<&'static str> {
+        match self {
+            SelinuxLabel::Shared => Some("z"),
+            SelinuxLabel::Private => Some("Z"),
+            SelinuxLabel::None => None,
+        }
+    }
+}
+
+/// A bind mount, given as `host:container[:ro|:rw]` or as an object.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(try_from = "VolumeSpec")]
+pub struct VolumeMount {
+    pub host: String,
+    pub container: String,
+    pub read_only: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum VolumeSpec {
+    Short(String),
+    Long {
+        host: String,
+        container: String,
+        #[serde(default)]
+        read_only: bool,
+    },
+}
+
+/// When `docker run` pulls the image.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PullPolicy {
+    Always,
+    Missing,
+    Never,
+}
+
+impl PullPolicy {
+    fn as_str(self) -> &'static str {
+        match self {
+            PullPolicy::Always => "always",
+            PullPolicy::Missing => "missing",
+            PullPolicy::Never => "never",
+        }
+    }
+}
+
+impl VolumeMount {
+    /// Parse `host:container[:ro|:rw]`. The host side may itself contain a
+    /// colon (Windows drive letters), so the spec is split from the right.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        let (rest, read_only) = match spec.rsplit_once(':') {
+            Some((rest, "ro")) => (rest, true),
+            Some((rest, "rw")) => (rest, false),
+            _ => (spec, false),
+        };
+        let (host, container) = rest
+            .rsplit_once(':')
+            .ok_or_else(|| format!("Invalid volume '{spec}': expected host:container[:ro]"))?;
+        Self::new(host, container, read_only)
+    }
+
+    fn new(host: &str, container: &str, read_only: bool) -> Result<Self, String> {
+        if host.is_empty() {
+            return Err(format!("Invalid volume for '{container}': host path is empty"));
+        }
+        if !container.starts_with('/') || container.contains(':') {
+            return Err(format!("Invalid volume container path '{container}': must be absolute"));
+        }
+        Ok(Self { host: host.to_string(), container: container.to_string(), read_only })
+    }
+
+    fn to_arg(&self, label: Option<&str>) -> String {
+        let options: Vec<&str> = self.read_only.then_some("ro").into_iter().chain(label).collect();
+        if options.is_empty() {
+            format!("{}:{}", self.host, self.container)
+        } else {
+            format!("{}:{}:{}", self.host, self.container, options.join(","))
+        }
+    }
+}
+
+impl TryFrom<VolumeSpec> for VolumeMount {
+    type Error = String;
+
+    fn try_from(spec: VolumeSpec) -> Result<Self, String> {
+        match spec {
+            VolumeSpec::Short(spec) => Self::parse(&spec),
+            VolumeSpec::Long { host, container, read_only } => Self::new(&host, &container, read_only),
+        }
+    }
+}
+
+/// Container name for one node execution: `devflow-<run>-<node>`, restricted
+/// to the characters docker accepts. Outside a run a timestamp stands in.
+pub fn container_name(node_id: &str, run_id: Option<&str>) -> String {
+    let run = match run_id {
+        Some(run) => run.to_string(),
+        None => chrono::Utc::now().timestamp_millis().to_string(),
+    };
+    sanitize_file_component(&format!("devflow-{run}-{node_id}"))
+}
+
+/// Host `UID:GID`, for running the container as the current user.
+#[cfg(unix)]
+fn host_user() -> Option<String> {
+    // SAFETY: getuid/getgid have no preconditions and cannot fail
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    Some(format!("{uid}:{gid}"))
+}
+
+#[cfg(not(unix))]
+fn host_user() -> Option<String> {
+    // Docker Desktop on Windows already maps bind-mount ownership
+    None
+}
+
+// ── Container runtime ─────────────────────────────────────────────────────────
+
+/// Runtimes that answered a probe, so each node doesn't pay for one.
+static PROBED: Mutex<Vec<RuntimeInfo>> = Mutex::new(Vec::new());
+
+/// Ask a runtime's engine for its version and rootless mode. `None` if the
+/// CLI is missing or can't reach its daemon / storage.
+async fn probe(runtime: ContainerRuntime) -> Option<RuntimeInfo> {
+    let format = match runtime {
+        ContainerRuntime::Docker => "{{.ServerVersion}} {{json .SecurityOptions}}",
+        ContainerRuntime::Podman => "{{.Version.Version}} {{.Host.Security.Rootless}}",
+    };
+    let output = tokio::time::timeout(
+        PROBE_TIMEOUT,
+        tokio::process::Command::new(runtime.binary())
+            .args(["info", "--format", format])
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_probe(runtime, &String::from_utf8_lossy(&output.stdout), selinux_enforcing())
+}
+
+fn parse_probe(runtime: ContainerRuntime, output: &str, selinux: bool) -> Option<RuntimeInfo> {
+    let (version, rest) = output.trim().split_once(' ').unwrap_or((output.trim(), ""));
+    if version.is_empty() {
+        return None;
+    }
+    let rootless = match runtime {
+        ContainerRuntime::Docker => rest.contains("name=rootless"),
+        ContainerRuntime::Podman => rest.trim() == "true",
+    };
+    Some(RuntimeInfo { runtime, version: version.to_string(), rootless, selinux })
+}
+
+fn selinux_enforcing() -> bool {
+    std::fs::read_to_string("/sys/fs/selinux/enforce").is_ok_and(|mode| mode.trim() == "1")
+}
+
+async fn probe_cached(runtime: ContainerRuntime) -> Option<RuntimeInfo> {
+    let cached = PROBED.lock().unwrap().iter().find(|info| info.runtime == runtime).cloned();
+    if cached.is_some() {
+        return cached;
+    }
+    let info = probe(runtime).await?;
+    PROBED.lock().unwrap().push(info.clone());
+    Some(info)
+}
+
+/// The runtime a node should use: the configured one, or else the first of
+/// Docker and Podman that responds.
+pub async fn resolve_runtime(preferred: Option<ContainerRuntime>) -> Result<RuntimeInfo, String> {
+    let candidates = match preferred {
+        Some(runtime) => vec![runtime],
+        None => vec![ContainerRuntime::Docker, ContainerRuntime::Podman],
+    };
+    for runtime in candidates {
+        if let Some(info) = probe_cached(runtime).await {
+            return Ok(info);
+        }
+    }
+    Err(match preferred {
+        Some(runtime) => format!("{} is not installed or not running", runtime.binary()),
+        None => "No container runtime found: install Docker or Podman".to_string(),
+    })
+}
+
+/// Probe the host afresh, e.g. after the user started a daemon.
+pub async fn detect_runtime() -> Option<RuntimeInfo> {
+    PROBED.lock().unwrap().clear();
+    resolve_runtime(None).await.ok()
+}
+
+/// Which container runtime the Docker profile would use on this machine.
+#[tauri::command]
+pub async fn probe_container_runtime() -> Result<Option<RuntimeInfo>, String> {
+    Ok(detect_runtime().await)
+}
+
+// ── Run arguments ─────────────────────────────────────────────────────────────
+
+/// Whether a node env var should be forwarded into the Docker container.
+pub fn forwards_to_container(cfg: &DockerConfig, name: &str) -> bool {
+    env_allowed(cfg.env_allowlist.as_deref(), name, "DOCKER_")
+}
+
+/// Render vars in docker's `--env-file` format (`NAME=value` per line, no
+/// quoting). Values can't span lines there, so multiline values are rejected.
+pub fn docker_env_file(vars: &[(String, SecretValue)]) -> Result<SecretValue, String> {
+    let mut contents = Zeroizing::new(String::new());
+    for (name, value) in vars {
+        if name.is_empty() || name.contains('=') || name.contains(char::is_whitespace) {
+            return Err(format!("Invalid environment variable name '{name}'"));
+        }
+        if value.contains('\n') || value.contains('\r') {
+            return Err(format!("${name} contains a newline, which docker --env-file can't represent"));
+        }
+        contents.push_str(name);
+        contents.push('=');
+        contents.push_str(value);
+        contents.push('\n');
+    }
+    Ok(contents)
+}
+
+/// Wraps a program for the Docker sandbox profile. Shell commands run under
+/// `sh -c` inside the container; argv programs are passed through as-is.
+pub fn wrap_for_docker(
+    program: &Program,
+    cwd: &Option<String>,
+    cfg: &DockerConfig,
+    runtime: &RuntimeInfo,
+    env_file: Option<&Path>,
+    name: &str,
+) -> Vec<String> {
+    let image = cfg.image.as_deref().unwrap_or(DEFAULT_IMAGE);
+    let work_dir = cwd.as_deref().unwrap_or(".");
+    let label = cfg
+        .selinux_label
+        .unwrap_or(if runtime.selinux { SelinuxLabel::Private } else { SelinuxLabel::None })
+        .mount_option();
+
+    let mut argv: Vec<String> = vec![runtime.runtime.binary().into(), "run".into(), "--rm".into()];
+    argv.extend(["--name".into(), name.to_string()]);
+    if let Some(cpu) = cfg.cpu_limit.as_deref() {
+        argv.push(format!("--cpus={}", cpu));
+    }
+    if let Some(mem) = cfg.mem_limit.as_deref() {
+        argv.push(format!("--memory={}", mem));
+    }
+    if let Some(shm) = cfg.shm_size.as_deref() {
+        argv.push(format!("--shm-size={}", shm));
+    }
+    if let Some(network) = cfg.network.as_deref() {
+        argv.push(format!("--network={}", network));
+    }
+    if let Some(pull) = cfg.pull {
+        argv.push(format!("--pull={}", pull.as_str()));
+    }
+    match cfg.user.as_deref() {
+        // Rootless Podman maps container root to the host user; keep-id maps
+        // the host UID/GID through unchanged instead
+        Some("host") if runtime.runtime == ContainerRuntime::Podman && runtime.rootless => {
+            argv.push("--userns=keep-id".into());
+        }
+        Some("host") => {
+            if let Some(user) = host_user() {
+                argv.extend(["--user".into(), user]);
+            }
+        }
+        Some(user) => argv.extend(["--user".into(), user.to_string()]),
+        None => {}
+    }
+    if let Some(entrypoint) = cfg.entrypoint.as_deref() {
+        argv.extend(["--entrypoint".into(), entrypoint.to_string()]);
+    }
+    if let Some(path) = env_file {
+        argv.extend(["--env-file".into(), path.to_string_lossy().into_owned()]);
+    }
+    let workspace = VolumeMount { host: work_dir.to_string(), container: "/workspace".into(), read_only: false };
+    argv.extend(["-v".into(), workspace.to_arg(label), "-w".into(), "/workspace".into()]);
+    for volume in &cfg.volumes {
+        argv.extend(["-v".into(), volume.to_arg(label)]);
+    }
+    argv.push(image.to_string());
+
+    match program {
+        Program::Shell(command) => argv.extend(["sh".into(), "-c".into(), command.clone()]),
+        Program::Argv(inner) => argv.extend(inner.iter().cloned()),
+    }
+    argv
+}
+
+/// Force-remove a node's container. Killing the `docker run` client alone
+/// leaves the container running inside the daemon.
+pub async fn remove_container(runtime: ContainerRuntime, name: &str) -> bool {
+    tokio::process::Command::new(runtime.binary())
+        .args(["rm", "-f", name])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn docker() -> RuntimeInfo {
+        RuntimeInfo { runtime: ContainerRuntime::Docker, version: "27.0.3".into(), rootless: false, selinux: false }
+    }
+
+    fn rootless_podman() -> RuntimeInfo {
+        RuntimeInfo { runtime: ContainerRuntime::Podman, version: "5.2.2".into(), rootless: true, selinux: true }
+    }
+
+    #[test]
+    fn test_docker_wrap_basic() {
+        let cfg = DockerConfig {
+            image: Some("ubuntu:22.04".to_string()),
+            cpu_limit: Some("0.5".to_string()),
+            mem_limit: Some("256m".to_string()),
+            ..Default::default()
+        };
+        let result = wrap_for_docker(&Program::Shell("echo hello".into()), &Some("/tmp".to_string()), &cfg, &docker(), None, "c");
+        assert_eq!(result[..3], ["docker", "run", "--rm"]);
+        assert!(result.contains(&"--cpus=0.5".to_string()));
+        assert!(result.contains(&"--memory=256m".to_string()));
+        assert!(result.contains(&"ubuntu:22.04".to_string()));
+        assert_eq!(result.last().unwrap(), "echo hello");
+    }
+
+    #[test]
+    fn test_docker_wrap_argv_passes_through() {
+        let argv = Program::Argv(vec!["git".into(), "pull".into(), "origin".into(), "a b".into()]);
+        let result = wrap_for_docker(&argv, &Some("/my project".to_string()), &DockerConfig::default(), &docker(), None, "c");
+        assert!(result.contains(&"/my project:/workspace".to_string()));
+        assert_eq!(result[result.len() - 4..], ["git", "pull", "origin", "a b"]);
+    }
+
+    #[test]
+    fn test_docker_wrap_env_file() {
+        let result = wrap_for_docker(
+            &Program::Shell("env".into()), &None, &DockerConfig::default(), &docker(), Some(Path::new("/tmp/devflow-env-1")), "c",
+        );
+        let at = result.iter().position(|a| a == "--env-file").expect("--env-file passed");
+        assert_eq!(result[at + 1], "/tmp/devflow-env-1");
+        // Options must come before the image
+        assert!(at < result.iter().position(|a| a == "ubuntu:22.04").unwrap());
+    }
+
+    #[test]
+    fn test_docker_wrap_extended_options() {
+        let cfg: DockerConfig = serde_json::from_value(serde_json::json!({
+            "volumes": ["/data:/data:ro", { "host": "/cache", "container": "/root/.cache" }],
+            "network": "none",
+            "user": "1000:1000",
+            "pull": "never",
+            "entrypoint": "/bin/bash",
+            "shm_size": "1g"
+        }))
+        .unwrap();
+        let result = wrap_for_docker(&Program::Shell("true".into()), &None, &cfg, &docker(), None, "devflow-r1-build");
+        let image_at = result.iter().position(|a| a == DEFAULT_IMAGE).unwrap();
+        let opts = &result[..image_at];
+        for expected in [
+            "--name", "devflow-r1-build", "/data:/data:ro", "/cache:/root/.cache", "--network=none",
+            "--user", "1000:1000", "--pull=never", "--entrypoint", "/bin/bash", "--shm-size=1g",
+        ] {
+            assert!(opts.contains(&expected.to_string()), "missing {expected} in {opts:?}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_docker_user_host_maps_uid_gid() {
+        let cfg = DockerConfig { user: Some("host".into()), ..Default::default() };
+        let result = wrap_for_docker(&Program::Shell("id".into()), &None, &cfg, &docker(), None, "c");
+        let at = result.iter().position(|a| a == "--user").unwrap();
+        assert_eq!(result[at + 1], host_user().unwrap());
+    }
+
+    #[test]
+    fn test_podman_rootless_keeps_id_and_relabels() {
+        let cfg = DockerConfig {
+            user: Some("host".into()),
+            volumes: vec![VolumeMount::parse("/data:/data:ro").unwrap()],
+            ..Default::default()
+        };
+        let result = wrap_for_docker(&Program::Shell("id".into()), &Some("/p".into()), &cfg, &rootless_podman(), None, "c");
+        assert_eq!(result[..2], ["podman", "run"]);
+        assert!(result.contains(&"--userns=keep-id".to_string()));
+        assert!(!result.contains(&"--user".to_string()));
+        assert!(result.contains(&"/p:/workspace:Z".to_string()));
+        assert!(result.contains(&"/data:/data:ro,Z".to_string()));
+
+        let shared = DockerConfig { selinux_label: Some(SelinuxLabel::Shared), ..Default::default() };
+        let result = wrap_for_docker(&Program::Shell("id".into()), &Some("/p".into()), &shared, &rootless_podman(), None, "c");
+        assert!(result.contains(&"/p:/workspace:z".to_string()));
+    }
+
+    #[test]
+    fn test_parse_probe() {
+        let info = parse_probe(ContainerRuntime::Podman, "5.2.2 true\n", false).unwrap();
+        assert_eq!((info.version.as_str(), info.rootless), ("5.2.2", true));
+
+        let info = parse_probe(ContainerRuntime::Docker, "27.0.3 [\"name=seccomp,profile=builtin\"]\n", true).unwrap();
+        assert!(!info.rootless && info.selinux);
+        let info = parse_probe(ContainerRuntime::Docker, "27.0.3 [\"name=rootless\"]", false).unwrap();
+        assert!(info.rootless);
+
+        assert!(parse_probe(ContainerRuntime::Docker, "\n", false).is_none());
+    }
+
+    #[test]
+    fn test_volume_parse() {
+        let v = VolumeMount::parse("C:\\data:/data:ro").unwrap();
+        assert_eq!((v.host.as_str(), v.container.as_str(), v.read_only), ("C:\\data", "/data", true));
+        assert!(!VolumeMount::parse("/a:/b:rw").unwrap().read_only);
+        assert!(VolumeMount::parse("/only-one-path").is_err());
+        assert!(VolumeMount::parse("/a:relative").is_err());
+    }
+
+    #[test]
+    fn test_container_name_is_deterministic_and_safe() {
+        assert_eq!(container_name("build", Some("run-1")), "devflow-run-1-build");
+        assert_eq!(container_name("a/b c", Some("r")), "devflow-r-a_b_c");
+    }
+
+    #[test]
+    fn test_docker_env_allowlist() {
+        let default = DockerConfig::default();
+        assert!(forwards_to_container(&default, "API_TOKEN"));
+        assert!(!forwards_to_container(&default, "DOCKER_HOST"));
+
+        let cfg = DockerConfig {
+            env_allowlist: Some(vec!["API_TOKEN".into(), "NPM_*".into()]),
+            ..Default::default()
+        };
+        assert!(forwards_to_container(&cfg, "API_TOKEN"));
+        assert!(forwards_to_container(&cfg, "NPM_CONFIG_REGISTRY"));
+        assert!(!forwards_to_container(&cfg, "HOME_DIR"));
+    }
+
+    #[test]
+    fn test_docker_env_file_format() {
+        let vars = vec![("A".to_string(), Zeroizing::new("x y=z".to_string()))];
+        assert_eq!(docker_env_file(&vars).unwrap().as_str(), "A=x y=z\n");
+
+        let multiline = vec![("KEY".to_string(), Zeroizing::new("line1\nline2".to_string()))];
+        assert!(docker_env_file(&multiline).is_err());
+    }
+}
@@ -1,17 +1,50 @@
 // ============================================================
 // DevFlow Studio — Command Executor (Rust)
 // Supports: native shell, Docker container, SSH remote profiles.
-// Features: per-node timeout, resource metrics, env var injection.
+// Runs either a shell command line or an argv spawned without a shell.
+// Features: per-node timeout, process-tree resource metrics, env var injection,
+//           process-group kill with SIGTERM → SIGKILL escalation,
+//           bounded output with full logs spilled to disk,
+//           masking of injected secrets in every line,
+//           node outputs via ::set-output and $DEVFLOW_OUTPUT.
 // ============================================================
 
 use tokio::process::Command;
 use std::process::Stdio;
 use std::collections::HashMap;
-use tokio::io::{AsyncBufReadExt, BufReader};
-use std::time::{Instant, Duration};
+use tokio::io::{AsyncRead, AsyncWriteExt};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Instant, Duration, SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
-use sysinfo::{Pid, System};
-use tauri::{AppHandle, Emitter};
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter, Manager, State};
+use zeroize::Zeroizing;
+
+use super::docker::{
+    container_name, docker_env_file, forwards_to_container, remove_container, resolve_runtime, wrap_for_docker,
+    DockerConfig, VolumeMount,
+};
+use super::limits::{Confinement, ResourceLimits};
+use super::log_event::{LogEmitter, LogEvent, LogLevel, LogStream};
+use super::metrics::{MetricsSampler, ResourceSample, ResourceSeries, SamplerSubscription};
+use super::node_outputs::{parse_directive, OutputFile, Outputs, CONTAINER_OUTPUT_PATH, OUTPUT_ENV};
+use super::output::{
+    read_output, sanitize_file_component, BoundedBuffer, LogSpill, LogSpillWriter, OutputEvent,
+};
+use super::redact::Redactor;
+use super::retry::{run_with_retry, Attempt, RetryPolicy};
+use super::sandbox::{sandbox_env, wrap_for_sandbox, SandboxConfig};
+use super::secret_file::SecretFile;
+use super::shell::ShellSpec;
+use super::termination::{StopCause, Termination};
+use super::secure_storage::{read_secret, SecretValue};
+use super::ssh::{
+    forwards_to_remote, identity_from_secret, known_hosts_path, wrap_for_ssh, SshConfig, SshFiles, SshMuxPool,
+};
+use super::process::{
+    isolate_process_group, terminate_tree, wait_finished, ProcessRegistry, Registration, DEFAULT_KILL_GRACE_SECS,
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ExecutionMetrics {
@@ -19,221 +52,640 @@ pub struct ExecutionMetrics {
     pub memory_mb: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// `stdout`/`stderr` hold at most a head and tail of each stream; the full
+/// output lives at `log_file_path`, if the log file could be written.
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CommandResult {
     pub stdout: String,
     pub stderr: String,
+    pub log_file_path: Option<String>,
+    pub truncated: bool,
     pub exit_code: i32,
     pub max_cpu: f32,
     pub max_memory_mb: u64,
+    pub max_threads: u32,
+    pub disk_read_bytes: u64,
+    pub disk_written_bytes: u64,
+    /// Resource usage over time, summed across the node's process tree.
+    pub samples: Vec<ResourceSample>,
     pub duration_ms: u64,
     pub timed_out: bool,
+    pub cancelled: bool,
+    /// Signal that finally ended a timed-out or cancelled process tree.
+    pub kill_signal: Option<String>,
+    /// Why the process ended; `exit_code` is -1 for anything but a normal exit.
+    pub termination: Termination,
+    /// Values published via `::set-output` lines or `$DEVFLOW_OUTPUT`.
+    pub outputs: Outputs,
+    /// Every run of the command under the node's retry policy, first to last.
+    pub attempts: Vec<Attempt>,
+}
+
+impl CommandResult {
+    /// Result of a node cancelled before its process was spawned.
+    pub fn cancelled_before_start(duration_ms: u64) -> Self {
+        Self {
+            stdout: String::new(),
+            stderr: String::new(),
+            log_file_path: None,
+            truncated: false,
+            exit_code: -1,
+            max_cpu: 0.0,
+            max_memory_mb: 0,
+            max_threads: 0,
+            disk_read_bytes: 0,
+            disk_written_bytes: 0,
+            samples: Vec::new(),
+            duration_ms,
+            timed_out: false,
+            cancelled: true,
+            kill_signal: None,
+            termination: Termination::Cancelled,
+            outputs: Outputs::new(),
+            attempts: Vec::new(),
+        }
+    }
 }
 
 /// Execution profile determines the sandbox environment.
 /// - `native`: run directly in the host shell (default)
-/// - `docker`: wrap in `docker run --rm` with optional CPU/memory limits
+/// - `docker`: wrap in `docker run --rm` (or `podman run --rm`) with optional CPU/memory limits
 /// - `ssh`: forward to a remote host via SSH
-#[derive(Debug, Deserialize, Clone)]
+/// - `sandbox`: run on the host under bubblewrap with a read-only filesystem (Linux)
+#[derive(Debug, Deserialize, Clone, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum ExecutionProfile {
+    #[default]
     Native,
     Docker,
     Ssh,
+    Sandbox,
+}
+
+/// An environment variable for the child: literal text, or a reference to a
+/// keychain secret (`{ "secret": "GITHUB_TOKEN" }`) that is resolved here in
+/// the backend, so the plaintext never passes through the webview.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum EnvValue {
+    Plain(String),
+    Secret { secret: String },
 }
 
-impl Default for ExecutionProfile {
-    fn default() -> Self { ExecutionProfile::Native }
+impl From<&str> for EnvValue {
+    fn from(value: &str) -> Self { EnvValue::Plain(value.to_string()) }
 }
 
-/// Configuration for the Docker execution profile.
-#[derive(Debug, Deserialize, Default, Clone)]
-pub struct DockerConfig {
-    pub image: Option<String>,
-    pub cpu_limit: Option<String>,
-    pub mem_limit: Option<String>,
+/// Whether a node env var is passed on to a sandbox (container, remote host).
+/// Entries are exact names or `PREFIX_*` patterns; without an allowlist every
+/// name except those starting with `client_prefix` (client-side config) passes.
+pub fn env_allowed(allowlist: Option<&[String]>, name: &str, client_prefix: &str) -> bool {
+    match allowlist {
+        Some(allowlist) => allowlist.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == pattern,
+        }),
+        None => !name.starts_with(client_prefix),
+    }
+}
+
+/// Child environment with secret references resolved.
+struct ResolvedEnv {
+    /// Every value is held in a zeroizing buffer until it is handed to the child.
+    vars: Vec<(String, SecretValue)>,
+    /// Masks the values that came from secret references.
+    redactor: Redactor,
+}
+
+/// Resolve env values, looking up secret references with `lookup`.
+fn resolve_env<F>(env_vars: HashMap<String, EnvValue>, lookup: F) -> Result<ResolvedEnv, String>
+where
+    F: Fn(&str) -> Result<Option<SecretValue>, String>,
+{
+    let mut vars = Vec::with_capacity(env_vars.len());
+    let mut secrets = Vec::new();
+    for (name, value) in env_vars {
+        match value {
+            EnvValue::Plain(text) => vars.push((name, Zeroizing::new(text))),
+            EnvValue::Secret { secret } => {
+                let resolved = lookup(&secret)?
+                    .ok_or_else(|| format!("Secret '{secret}' for ${name} not found in OS keychain"))?;
+                secrets.push(resolved.clone());
+                vars.push((name, resolved));
+            }
+        }
+    }
+    let redactor = Redactor::new(secrets.iter().map(|s| s.as_str()));
+    Ok(ResolvedEnv { vars, redactor })
 }
 
-/// Configuration for the SSH execution profile.
-#[derive(Debug, Deserialize, Default, Clone)]
-pub struct SshConfig {
-    pub host: Option<String>,
-    pub user: Option<String>,
+/// What to run: a shell command line, or a program with arguments spawned directly.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Program {
+    /// Passed to a shell (see `ShellSpec`), for user-written scripts.
+    Shell(String),
+    /// `argv[0]` is executed with the remaining arguments, no shell involved.
+    Argv(Vec<String>),
 }
 
-/// Wraps a shell command for the Docker sandbox profile.
-fn wrap_for_docker(command: &str, cwd: &Option<String>, cfg: &DockerConfig) -> String {
-    let image = cfg.image.as_deref().unwrap_or("ubuntu:22.04");
-    let work_dir = cwd.as_deref().unwrap_or(".");
-    let cpu = cfg.cpu_limit.as_deref().map(|c| format!("--cpus={}", c)).unwrap_or_default();
-    let mem = cfg.mem_limit.as_deref().map(|m| format!("--memory={}", m)).unwrap_or_default();
-
-    format!(
-        "docker run --rm {} {} -v {}:/workspace -w /workspace {} sh -c {}",
-        cpu, mem,
-        work_dir,
-        image,
-        shell_escape(command)
-    )
+impl Default for Program {
+    fn default() -> Self { Program::Shell(String::new()) }
 }
 
-/// Wraps a shell command for SSH remote execution.
-fn wrap_for_ssh(command: &str, cfg: &SshConfig) -> String {
-    let user = cfg.user.as_deref().unwrap_or("root");
-    let host = cfg.host.as_deref().unwrap_or("localhost");
-    format!("ssh -o StrictHostKeyChecking=no {}@{} {}", user, host, shell_escape(command))
+impl Program {
+    /// Build from `execute_command`'s arguments; exactly one must be given.
+    pub fn from_args(command: Option<String>, argv: Option<Vec<String>>) -> Result<Self, String> {
+        match (command, argv) {
+            (Some(command), None) => Ok(Program::Shell(command)),
+            (None, Some(argv)) if !argv.is_empty() => Ok(Program::Argv(argv)),
+            (None, Some(_)) => Err("argv must not be empty".into()),
+            _ => Err("Provide exactly one of command or argv".into()),
+        }
+    }
+
+    /// Single string for a remote shell (SSH): argv elements are escaped individually.
+    pub fn to_shell_string(&self) -> String {
+        match self {
+            Program::Shell(command) => command.clone(),
+            Program::Argv(argv) => argv.iter().map(|a| shell_escape(a)).collect::<Vec<_>>().join(" "),
+        }
+    }
 }
 
 /// Shell-escape a command string for embedding in a shell -c argument.
-fn shell_escape(cmd: &str) -> String {
+pub fn shell_escape(cmd: &str) -> String {
     format!("'{}'", cmd.replace('\'', "'\\''"))
 }
 
-/// Determine the host shell to use.
-fn detect_shell() -> (String, String) {
-    if cfg!(target_os = "windows") {
-        ("powershell.exe".to_string(), "-Command".to_string())
-    } else {
-        let env_shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string());
-        (env_shell, "-c".to_string())
+/// Minimum gap between progress events for one stream.
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Per-execution log file location, under the app log directory:
+/// `executions/<run_id>/<node_id>.log`, or a timestamped name outside a run.
+fn execution_log_path(app: &AppHandle, node_id: &str, run_id: Option<&str>) -> Option<PathBuf> {
+    let base = app.path().app_log_dir().ok()?.join("executions");
+    let node = sanitize_file_component(node_id);
+    Some(match run_id {
+        Some(run) => base.join(sanitize_file_component(run)).join(format!("{node}.log")),
+        None => {
+            let ts = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+            base.join(format!("{node}-{ts}.log"))
+        }
+    })
+}
+
+/// Read one child stream to EOF, sending a `LogEvent` for every complete line
+/// and throttled progress events for `\r` overwrites. Every line goes to the
+/// spill file; only a bounded head/tail is kept in memory. Secrets are masked
+/// before a line reaches any of them. `::set-output` directives on stdout are
+/// collected as the node's outputs.
+async fn capture_stream<R>(
+    log: LogEmitter,
+    stream: LogStream,
+    reader: R,
+    spill: Option<LogSpillWriter>,
+    redactor: Arc<Redactor>,
+) -> (BoundedBuffer, Outputs)
+where
+    R: AsyncRead + Unpin,
+{
+    let mut lines = BoundedBuffer::default();
+    let mut outputs = Outputs::new();
+    let mut last_progress: Option<Instant> = None;
+
+    read_output(reader, |event| match event {
+        OutputEvent::Line(line) => {
+            if stream == LogStream::Stdout {
+                if let Some((name, value)) = parse_directive(&line) {
+                    outputs.insert(name, redactor.redact(&value).into_owned());
+                }
+            }
+            let line = redactor.redact(&line).into_owned();
+            log.line(stream, line.clone());
+            if let Some(ref spill) = spill {
+                spill.write(stream.as_str(), &line);
+            }
+            lines.push(line);
+        }
+        OutputEvent::Progress(text) => {
+            if last_progress.map_or(true, |t| t.elapsed() >= PROGRESS_EMIT_INTERVAL) {
+                last_progress = Some(Instant::now());
+                log.progress(stream, redactor.redact(&text).into_owned());
+            }
+        }
+    })
+    .await;
+
+    (lines, outputs)
+}
+
+/// Next sample for this execution, or pending forever when not sampling.
+async fn next_sample(sub: &mut Option<SamplerSubscription>) -> Option<ResourceSample> {
+    match sub {
+        Some(sub) => sub.recv().await,
+        None => std::future::pending().await,
     }
 }
 
+/// Everything needed to run one node's command. Built by `execute_command`
+/// from its IPC arguments, and by the flow engine from a `FlowNode`.
+#[derive(Debug, Clone, Default)]
+pub struct ExecRequest {
+    pub node_id: String,
+    pub run_id: Option<String>,
+    pub program: Program,
+    /// Interpreter for `Program::Shell` on the host (native, sandbox).
+    pub shell: ShellSpec,
+    pub cwd: Option<String>,
+    pub env_vars: HashMap<String, EnvValue>,
+    pub timeout_seconds: Option<u64>,
+    pub profile: ExecutionProfile,
+    pub docker_config: DockerConfig,
+    pub ssh_config: SshConfig,
+    pub sandbox_config: SandboxConfig,
+    /// cgroup limits for profiles that run on the host (native, sandbox).
+    pub limits: ResourceLimits,
+    pub kill_grace_seconds: Option<u64>,
+}
+
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn execute_command(
     app: AppHandle,
     node_id: String,
-    command: String,
+    run_id: Option<String>,
+    command: Option<String>,
+    argv: Option<Vec<String>>,
+    shell: Option<ShellSpec>,
     cwd: Option<String>,
-    env_vars: Option<HashMap<String, String>>,
+    env_vars: Option<HashMap<String, EnvValue>>,
     timeout_seconds: Option<u64>,
     profile: Option<ExecutionProfile>,
     docker_config: Option<DockerConfig>,
     ssh_config: Option<SshConfig>,
+    sandbox_config: Option<SandboxConfig>,
+    resource_limits: Option<ResourceLimits>,
+    kill_grace_seconds: Option<u64>,
+    retry_policy: Option<RetryPolicy>,
+    allowed_exit_codes: Option<Vec<i32>>,
+    on_event: Channel<LogEvent>,
 ) -> Result<CommandResult, String> {
+    let program = Program::from_args(command, argv)?;
+    let log = LogEmitter::new(on_event, &node_id, run_id.as_deref());
+    let request = ExecRequest {
+        node_id,
+        run_id,
+        program,
+        shell: shell.unwrap_or_default(),
+        cwd,
+        env_vars: env_vars.unwrap_or_default(),
+        timeout_seconds,
+        profile: profile.unwrap_or_default(),
+        docker_config: docker_config.unwrap_or_default(),
+        ssh_config: ssh_config.unwrap_or_default(),
+        sandbox_config: sandbox_config.unwrap_or_default(),
+        limits: resource_limits.unwrap_or_default(),
+        kill_grace_seconds,
+    };
+    let allowed_exit_codes = allowed_exit_codes.unwrap_or_default();
+    run_with_retry(&app, request, &retry_policy.unwrap_or_default(), &allowed_exit_codes, log).await
+}
+
+/// Spawn one node's command and drive it to completion: stream its output,
+/// sample its process tree, and enforce timeout and cancellation. The caller
+/// registers the node before calling and finishes the registration, so a
+/// cancel during setup or between retries isn't lost.
+pub async fn run_command(
+    app: &AppHandle,
+    req: ExecRequest,
+    log: LogEmitter,
+    registration: &Registration,
+) -> Result<CommandResult, String> {
+    let ExecRequest {
+        node_id,
+        run_id,
+        program,
+        shell,
+        cwd,
+        env_vars,
+        timeout_seconds,
+        profile,
+        mut docker_config,
+        ssh_config,
+        mut sandbox_config,
+        limits,
+        kill_grace_seconds,
+    } = req;
+    let sampler = app.state::<MetricsSampler>();
+
+    // Resolve secret references before anything is spawned
+    let ResolvedEnv { vars: mut env_vars, redactor } = resolve_env(env_vars, read_secret)?;
+    let redactor = Arc::new(redactor);
+
     let start_time = Instant::now();
     let timeout_secs = timeout_seconds.unwrap_or(300);
+    let kill_grace = Duration::from_secs(kill_grace_seconds.unwrap_or(DEFAULT_KILL_GRACE_SECS));
+    let is_ssh = matches!(profile, ExecutionProfile::Ssh);
+
+    // ── Build the host argv based on execution profile ────────────────────────
+    // Temp files below are private (0600) and removed when this function returns.
+    let mut container = None;
+    let mut env_file = None;
+    let mut stdin_script = None;
+    let key_file = match profile {
+        ExecutionProfile::Ssh => identity_from_secret(&ssh_config)?,
+        _ => None,
+    };
+    // Remote hosts can't write a local file; they publish via stdout only
+    let output_file = match profile {
+        ExecutionProfile::Ssh => None,
+        _ => Some(OutputFile::create()?),
+    };
+    let output_path = output_file.as_ref().map(|f| f.path().to_string_lossy().into_owned());
 
-    // ── Build the actual command based on execution profile ────────────────────
-    let resolved_command = match profile.unwrap_or_default() {
+    let host_argv = match profile {
         ExecutionProfile::Docker => {
-            wrap_for_docker(&command, &cwd, &docker_config.unwrap_or_default())
+            // Forwarded vars go into the container via an env file, the rest stay on the host CLI
+            let (mut forward, host): (Vec<_>, Vec<_>) = env_vars
+                .into_iter()
+                .partition(|(name, _)| forwards_to_container(&docker_config, name));
+            env_vars = host;
+            if let Some(path) = output_path.clone() {
+                forward.push((OUTPUT_ENV.to_string(), Zeroizing::new(CONTAINER_OUTPUT_PATH.to_string())));
+                docker_config.volumes.push(VolumeMount {
+                    host: path,
+                    container: CONTAINER_OUTPUT_PATH.into(),
+                    read_only: false,
+                });
+            }
+            if !forward.is_empty() {
+                let contents = docker_env_file(&forward)?;
+                env_file = Some(
+                    SecretFile::create("env", contents.as_bytes())
+                        .map_err(|e| format!("Failed to write docker env file: {e}"))?,
+                );
+            }
+            let runtime = resolve_runtime(docker_config.runtime).await?;
+            let (_, name) = container.insert((runtime.runtime, container_name(&node_id, run_id.as_deref())));
+            wrap_for_docker(&program, &cwd, &docker_config, &runtime, env_file.as_ref().map(SecretFile::path), name)
         }
         ExecutionProfile::Ssh => {
-            wrap_for_ssh(&command, &ssh_config.unwrap_or_default())
+            // Forwarded vars are exported by a script on the remote `sh -s` stdin
+            let (forward, host): (Vec<_>, Vec<_>) = env_vars
+                .into_iter()
+                .partition(|(name, _)| forwards_to_remote(&ssh_config, name));
+            env_vars = host;
+            let known_hosts = known_hosts_path(app)?;
+            // Nodes of the same run reuse one connection per destination
+            let control_dir = run_id.as_deref().and_then(|id| app.state::<SshMuxPool>().control_dir(id));
+            let files = SshFiles {
+                known_hosts: &known_hosts,
+                identity: key_file.as_ref().map(SecretFile::path),
+                control_dir: control_dir.as_deref(),
+            };
+            let invocation = wrap_for_ssh(&program, &ssh_config, &files, &forward)?;
+            stdin_script = invocation.stdin_script;
+            invocation.argv
         }
-        ExecutionProfile::Native => command.clone(),
+        ExecutionProfile::Sandbox => {
+            // Binds come after the tmpfs /tmp, so the output file stays visible
+            sandbox_config.read_write.extend(output_path.clone());
+            let program = match program {
+                Program::Shell(command) => Program::Argv(shell.argv(&command)?),
+                argv => argv,
+            };
+            wrap_for_sandbox(&program, &cwd, &sandbox_config)?
+        }
+        ExecutionProfile::Native => match program {
+            Program::Shell(command) => shell.argv(&command)?,
+            Program::Argv(argv) => argv,
+        },
     };
 
-    let (shell_bin, shell_flag) = detect_shell();
-
-    let mut cmd_builder = Command::new(&shell_bin);
-    cmd_builder.arg(&shell_flag).arg(&resolved_command);
+    let mut cmd_builder = Command::new(&host_argv[0]);
+    cmd_builder.args(&host_argv[1..]);
 
     if let Some(ref dir) = cwd {
         cmd_builder.current_dir(dir);
     }
 
-    if let Some(envs) = env_vars {
-        for (k, v) in envs {
-            cmd_builder.env(k, v);
+    // Sandboxed commands start from an allowlisted env, not the host's
+    if matches!(profile, ExecutionProfile::Sandbox) {
+        cmd_builder.env_clear().envs(sandbox_env(std::env::vars()));
+    }
+    for (k, v) in &env_vars {
+        cmd_builder.env(k, v.as_str());
+    }
+    if let Some(path) = output_path.as_ref().filter(|_| !matches!(profile, ExecutionProfile::Docker)) {
+        cmd_builder.env(OUTPUT_ENV, path);
+    }
+
+    // Own process group, so timeouts and cancellation reach grandchildren too
+    isolate_process_group(&mut cmd_builder);
+
+    // Containers and remote hosts enforce their own limits
+    let confinement = match profile {
+        ExecutionProfile::Native | ExecutionProfile::Sandbox => Confinement::new(&limits, &node_id)?,
+        _ => (Confinement::None, None),
+    };
+    let confinement = match confinement {
+        (confinement, Some(warning)) => {
+            log.system(LogLevel::Warn, warning);
+            confinement
         }
+        (confinement, None) => confinement,
+    };
+    confinement.apply(&mut cmd_builder);
+
+    if stdin_script.is_some() {
+        cmd_builder.stdin(Stdio::piped());
+    }
+
+    if registration.is_cancelled() {
+        log.system(LogLevel::Info, "Cancelled before start".into());
+        return Ok(CommandResult::cancelled_before_start(start_time.elapsed().as_millis() as u64));
     }
 
     let mut child = cmd_builder
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
-        .map_err(|e| format!("Failed to spawn command: {e}"))?;
+        .map_err(|e| format!("Failed to spawn {}: {e}", host_argv[0]))?;
 
+    if let (Some(script), Some(mut stdin)) = (stdin_script, child.stdin.take()) {
+        tokio::spawn(async move {
+            let _ = stdin.write_all(script.as_bytes()).await;
+            // Dropping stdin sends EOF, ending the remote `sh -s` script
+        });
+    }
+
+    // A cancel that lands between the check above and here left a permit on
+    // `registration.cancel`, so the wait loop below stops the child at once
     let pid = child.id().map(|id| id as usize);
     let stdout = child.stdout.take().unwrap();
     let stderr = child.stderr.take().unwrap();
 
-    // ── Async stdout reader ────────────────────────────────────────────────────
-    let app_clone = app.clone();
-    let node_id_stdout = node_id.clone();
-    let stdout_handle = tokio::spawn(async move {
-        let mut lines = Vec::new();
-        let mut reader = BufReader::new(stdout).lines();
-        while let Ok(Some(line)) = reader.next_line().await {
-            let _ = app_clone.emit("node-log", (node_id_stdout.clone(), "stdout", line.clone()));
-            lines.push(line);
-        }
-        lines
-    });
-
-    // ── Async stderr reader ────────────────────────────────────────────────────
-    let app_clone2 = app.clone();
-    let node_id_stderr = node_id.clone();
-    let stderr_handle = tokio::spawn(async move {
-        let mut lines = Vec::new();
-        let mut reader = BufReader::new(stderr).lines();
-        while let Ok(Some(line)) = reader.next_line().await {
-            let _ = app_clone2.emit("node-log", (node_id_stderr.clone(), "stderr", line.clone()));
-            lines.push(line);
-        }
-        lines
-    });
-
-    // ── Resource metrics polling + timeout ─────────────────────────────────────
-    let mut sys = System::new_all();
-    let mut max_cpu = 0.0f32;
-    let mut max_mem = 0u64;
+    // ── Full-output log file (best effort) ─────────────────────────────────────
+    let spill = match execution_log_path(app, &node_id, run_id.as_deref()) {
+        Some(path) => LogSpill::create(&path).await.ok(),
+        None => None,
+    };
+    let spill_writer = spill.as_ref().map(LogSpill::writer);
+
+    // ── Async stdout/stderr readers (byte-safe, `\r`-aware) ───────────────────
+    let stdout_handle = tokio::spawn(capture_stream(
+        log.clone(), LogStream::Stdout, stdout, spill_writer.clone(), redactor.clone(),
+    ));
+    let stderr_handle = tokio::spawn(capture_stream(
+        log.clone(), LogStream::Stderr, stderr, spill_writer, redactor.clone(),
+    ));
+
+    // ── Wait for exit, racing metrics ticks, timeout and cancellation ─────────
+    let mut metrics = pid.map(|p| sampler.watch(p as u32));
+    let mut series = ResourceSeries::default();
     let mut timed_out = false;
+    let mut cancelled = false;
+    let mut kill_signal = None;
 
-    let deadline = Instant::now() + Duration::from_secs(timeout_secs);
-
-    loop {
-        match child.try_wait() {
-            Ok(Some(_)) => break, // Process finished
-            Ok(None) => {
-                if Instant::now() > deadline {
-                    timed_out = true;
-                    // Kill the process tree
-                    let _ = child.kill().await;
-                    let _ = app.emit("node-log", (node_id.clone(), "error",
-                        format!("⏱ Command timed out after {}s", timeout_secs)));
-                    break;
-                }
-            }
-            Err(_) => break,
-        }
+    let deadline = tokio::time::sleep(Duration::from_secs(timeout_secs));
+    tokio::pin!(deadline);
 
-        // Sample resource usage
-        if let Some(p_id) = pid {
-            sys.refresh_process(Pid::from(p_id));
-            if let Some(p) = sys.process(Pid::from(p_id)) {
-                let cpu = p.cpu_usage();
-                let mem = p.memory() / 1024 / 1024;
-                if cpu > max_cpu { max_cpu = cpu; }
-                if mem > max_mem { max_mem = mem; }
+    let exited = loop {
+        tokio::select! {
+            status = child.wait() => break Some(status),
+            Some(sample) = next_sample(&mut metrics) => {
                 let _ = app.emit("execution-metrics", (node_id.clone(), ExecutionMetrics {
-                    cpu_usage: cpu,
-                    memory_mb: mem,
+                    cpu_usage: sample.cpu_usage,
+                    memory_mb: sample.memory_mb,
                 }));
+                series.push(sample);
+            }
+            _ = &mut deadline => {
+                timed_out = true;
+                log.system(LogLevel::Error, format!("⏱ Command timed out after {}s", timeout_secs));
+                break None;
+            }
+            _ = registration.cancel.notified() => {
+                cancelled = true;
+                log.system(LogLevel::Error, "⏹ Command cancelled".to_string());
+                break None;
+            }
+        }
+    };
+    drop(metrics);
+
+    let status = match exited {
+        Some(status) => status,
+        None => {
+            // Kill the process tree
+            kill_signal = terminate_tree(&mut child, pid.map(|p| p as u32), kill_grace).await;
+            // The container lives in the daemon, not our process tree
+            if let Some((runtime, ref name)) = container {
+                if remove_container(runtime, name).await {
+                    log.system(LogLevel::Info, format!("Removed container {name}"));
+                }
             }
+            child.wait().await
         }
-        tokio::time::sleep(Duration::from_millis(500)).await;
     }
-
-    let status = child.wait().await.map_err(|e| format!("Failed to wait: {e}"))?;
+    .map_err(|e| format!("Failed to wait: {e}"))?;
     let duration = start_time.elapsed().as_millis() as u64;
 
-    let stdout_lines = stdout_handle.await.unwrap_or_default();
-    let stderr_lines = stderr_handle.await.unwrap_or_default();
+    let (stdout_buf, mut outputs) = stdout_handle.await.unwrap_or_default();
+    let (stderr_buf, _) = stderr_handle.await.unwrap_or_default();
+    let truncated = stdout_buf.is_truncated() || stderr_buf.is_truncated();
 
-    let exit_code = if timed_out { -1 } else { status.code().unwrap_or(-1) };
+    let log_file_path = match spill {
+        Some(spill) => spill.close().await.ok().map(|p| p.to_string_lossy().into_owned()),
+        None => None,
+    };
 
-    Ok(CommandResult {
-        stdout: stdout_lines.join("\n"),
-        stderr: stderr_lines.join("\n"),
+    let exit_code = if timed_out || cancelled { -1 } else { status.code().unwrap_or(-1) };
+
+    let termination = Termination::from_status(&status, StopCause {
+        timed_out,
+        cancelled,
+        oom_killed: confinement.oom_killed(),
+        container: container.is_some(),
+    });
+    if let Err(e) = confinement.release().await {
+        log.system(LogLevel::Warn, format!("Resource limits: {e}"));
+    }
+    match termination {
+        Termination::OomKilled => {
+            log.system(LogLevel::Error, "Killed by the OOM killer: the node hit its memory limit".into());
+        }
+        Termination::Signaled { ref signal, core_dumped } => {
+            let core = if core_dumped { " (core dumped)" } else { "" };
+            log.system(LogLevel::Error, format!("Process killed by {signal}{core}"));
+        }
+        _ => {}
+    }
+
+    // The output file wins over stdout directives for the same name
+    match output_file.as_ref().map(OutputFile::read) {
+        Some(Ok(written)) => {
+            outputs.extend(written.into_iter().map(|(name, value)| (name, redactor.redact(&value).into_owned())))
+        }
+        Some(Err(e)) => log.system(LogLevel::Warn, format!("Ignoring ${OUTPUT_ENV}: {e}")),
+        None => {}
+    }
+
+    let stderr = stderr_buf.into_text(log_file_path.is_some());
+    if is_ssh && exit_code == 255 && stderr.contains("Host key verification failed") {
+        log.system(
+            LogLevel::Warn,
+            "The SSH host key is not trusted yet — verify its fingerprint and trust it in the node's SSH settings".into(),
+        );
+    }
+
+    let result = CommandResult {
+        stdout: stdout_buf.into_text(log_file_path.is_some()),
+        stderr,
+        log_file_path,
+        truncated,
         exit_code,
-        max_cpu,
-        max_memory_mb: max_mem,
+        max_cpu: series.max_cpu,
+        max_memory_mb: series.max_memory_mb,
+        max_threads: series.max_threads,
+        disk_read_bytes: series.disk_read_bytes,
+        disk_written_bytes: series.disk_written_bytes,
+        samples: series.into_samples(),
         duration_ms: duration,
         timed_out,
-    })
+        cancelled,
+        kill_signal,
+        termination,
+        outputs,
+        attempts: Vec::new(),
+    };
+    Ok(result)
+}
+
+/// Cancel in-flight executions by node ID, run ID, or both.
+/// Kills each matching child and returns its final `CommandResult`.
+#[tauri::command]
+pub async fn cancel_execution(
+    registry: State<'_, ProcessRegistry>,
+    node_id: Option<String>,
+    run_id: Option<String>,
+) -> Result<Vec<CommandResult>, String> {
+    if node_id.is_none() && run_id.is_none() {
+        return Err("cancel_execution requires a node_id or run_id".into());
+    }
+
+    let pending = registry.cancel(node_id.as_deref(), run_id.as_deref());
+    if pending.is_empty() {
+        return Err("No running execution matches the given node_id/run_id".into());
+    }
+
+    let mut results = Vec::with_capacity(pending.len());
+    for rx in pending {
+        if let Some(result) = wait_finished(rx).await {
+            results.push(result);
+        }
+    }
+    Ok(results)
 }
 
 // ── Unit tests ─────────────────────────────────────────────────────────────────
@@ -243,30 +695,37 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_docker_wrap_basic() {
-        let cfg = DockerConfig {
-            image: Some("ubuntu:22.04".to_string()),
-            cpu_limit: Some("0.5".to_string()),
-            mem_limit: Some("256m".to_string()),
-        };
-        let result = wrap_for_docker("echo hello", &Some("/tmp".to_string()), &cfg);
-        assert!(result.contains("docker run --rm"));
-        assert!(result.contains("--cpus=0.5"));
-        assert!(result.contains("--memory=256m"));
-        assert!(result.contains("ubuntu:22.04"));
-        assert!(result.contains("echo hello"));
+    fn test_env_value_deserializes_plain_and_secret() {
+        let env: HashMap<String, EnvValue> =
+            serde_json::from_str(r#"{ "A": "1", "TOKEN": { "secret": "GITHUB_TOKEN" } }"#).unwrap();
+        assert_eq!(env["A"], EnvValue::Plain("1".into()));
+        assert_eq!(env["TOKEN"], EnvValue::Secret { secret: "GITHUB_TOKEN".into() });
     }
 
     #[test]
-    fn test_ssh_wrap_basic() {
-        let cfg = SshConfig {
-            user: Some("deploy".to_string()),
-            host: Some("prod.example.com".to_string()),
-        };
-        let result = wrap_for_ssh("ls -la", &cfg);
-        assert!(result.contains("ssh"));
-        assert!(result.contains("deploy@prod.example.com"));
-        assert!(result.contains("ls -la"));
+    fn test_resolve_env_looks_up_secrets() {
+        let env = HashMap::from([
+            ("A".to_string(), EnvValue::from("plain")),
+            ("TOKEN".to_string(), EnvValue::Secret { secret: "GITHUB_TOKEN".into() }),
+        ]);
+        let lookup = |key: &str| Ok((key == "GITHUB_TOKEN").then(|| Zeroizing::new("s3cret".to_string())));
+        let mut resolved = resolve_env(env, lookup).unwrap();
+        resolved.vars.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(resolved.vars[0].1.as_str(), "plain");
+        assert_eq!(resolved.vars[1].1.as_str(), "s3cret");
+        // Only the secret is masked, not the plain value
+        assert_eq!(resolved.redactor.redact("plain s3cret"), "plain ***");
+
+        let missing = HashMap::from([("X".to_string(), EnvValue::Secret { secret: "NOPE".into() })]);
+        assert!(resolve_env(missing, lookup).err().unwrap().contains("NOPE"));
+    }
+
+    #[test]
+    fn test_program_from_args() {
+        assert_eq!(Program::from_args(Some("ls".into()), None).unwrap(), Program::Shell("ls".into()));
+        assert!(Program::from_args(None, Some(vec![])).is_err());
+        assert!(Program::from_args(Some("ls".into()), Some(vec!["ls".into()])).is_err());
+        assert!(Program::from_args(None, None).is_err());
     }
 
     #[test]
@@ -276,11 +735,4 @@ mod tests {
         assert!(escaped.ends_with('\''));
         assert!(!escaped.contains("'hello world'")); // single quotes escaped
     }
-
-    #[test]
-    fn test_detect_shell_not_empty() {
-        let (shell, flag) = detect_shell();
-        assert!(!shell.is_empty());
-        assert!(!flag.is_empty());
-    }
 }
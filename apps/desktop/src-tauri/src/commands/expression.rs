@@ -0,0 +1,622 @@
+// ============================================================
+// DevFlow Studio — Condition Expressions
+// A small expression language for `conditionalNode`, e.g.
+//   exit_code == 0 && !contains(stderr, 'warning')
+//   nodes.build.outputs.tag =~ '^v\d+' || env.CI == 'true'
+// Literals, references to upstream results, comparisons,
+// `&&` / `||` / `!` and a fixed set of string functions.
+// Nothing can execute code or touch the filesystem. Errors
+// carry a 1-based column so the editor can point at them.
+// ============================================================
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::node_outputs::Outputs;
+
+/// Fields of a node's result that expressions can read.
+const NODE_FIELDS: &[&str] = &["status", "exit_code", "duration_ms", "timed_out", "stdout", "stderr"];
+
+/// A parse or evaluation error, positioned at a character column.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExprError {
+    pub message: String,
+    /// 1-based, counted in characters.
+    pub column: usize,
+}
+
+impl ExprError {
+    fn new(message: impl Into<String>, column: usize) -> Self {
+        Self { message: message.into(), column }
+    }
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (column {})", self.message, self.column)
+    }
+}
+
+// ── Evaluation context ────────────────────────────────────────────────────────
+
+/// What a finished node exposes to expressions.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct NodeFacts {
+    pub status: String,
+    pub exit_code: Option<i32>,
+    pub duration_ms: Option<u64>,
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+    pub outputs: Outputs,
+}
+
+/// Everything an expression can reference. Bare names (`exit_code`,
+/// `outputs.tag`) refer to the single upstream node.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ExprContext {
+    /// IDs of the nodes with an edge into the one being evaluated.
+    pub upstream: Vec<String>,
+    pub nodes: HashMap<String, NodeFacts>,
+    pub env: HashMap<String, String>,
+}
+
+impl ExprContext {
+    /// Fill `env` with the app's environment, keeping values already set.
+    pub fn with_host_env(mut self) -> Self {
+        for (name, value) in std::env::vars() {
+            self.env.entry(name).or_insert(value);
+        }
+        self
+    }
+
+    fn upstream_node(&self, column: usize) -> Result<&NodeFacts, ExprError> {
+        let id = match self.upstream.as_slice() {
+            [id] => id,
+            [] => return Err(ExprError::new("No upstream node to read from", column)),
+            _ => {
+                return Err(ExprError::new(
+                    format!("Several upstream nodes ({}): use nodes.<id>.<field>", self.upstream.join(", ")),
+                    column,
+                ))
+            }
+        };
+        self.node(id, column)
+    }
+
+    fn node(&self, id: &str, column: usize) -> Result<&NodeFacts, ExprError> {
+        self.nodes.get(id).ok_or_else(|| ExprError::new(format!("Node '{id}' has not run"), column))
+    }
+}
+
+// ── Values ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+enum Val {
+    Null,
+    Bool(bool),
+    Num(f64),
+    Str(String),
+}
+
+impl Val {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Val::Null => "null",
+            Val::Bool(_) => "boolean",
+            Val::Num(_) => "number",
+            Val::Str(_) => "string",
+        }
+    }
+
+    fn truthy(&self) -> bool {
+        match self {
+            Val::Null => false,
+            Val::Bool(b) => *b,
+            Val::Num(n) => *n != 0.0,
+            Val::Str(s) => !s.is_empty(),
+        }
+    }
+
+    /// Outputs and env values are strings; they compare as numbers against numbers.
+    fn as_num(&self) -> Option<f64> {
+        match self {
+            Val::Num(n) => Some(*n),
+            Val::Str(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+fn equals(a: &Val, b: &Val) -> bool {
+    match (a, b) {
+        (Val::Num(_), Val::Str(_)) | (Val::Str(_), Val::Num(_)) => a.as_num().is_some_and(|x| b.as_num() == Some(x)),
+        _ => a == b,
+    }
+}
+
+fn compare(a: &Val, b: &Val, column: usize) -> Result<Ordering, ExprError> {
+    let ordering = match (a, b) {
+        (Val::Str(x), Val::Str(y)) => Some(x.cmp(y)),
+        (Val::Num(_), _) | (_, Val::Num(_)) => a.as_num().zip(b.as_num()).and_then(|(x, y)| x.partial_cmp(&y)),
+        _ => None,
+    };
+    ordering.ok_or_else(|| ExprError::new(format!("Cannot compare {} with {}", a.type_name(), b.type_name()), column))
+}
+
+// ── Lexer ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Str(String),
+    /// A name or dotted path: `exit_code`, `nodes.build.outputs.tag`.
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+const OPERATORS: &[&str] = &["==", "!=", "<=", ">=", "=~", "&&", "||", "<", ">", "!"];
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+/// Node IDs may contain `-`; there is no subtraction to confuse it with.
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+fn tokenize(src: &str) -> Result<Vec<(Token, usize)>, ExprError> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let token = match c {
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            ',' => Token::Comma,
+            '\'' | '"' => {
+                let mut text = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(ExprError::new("Unterminated string", column)),
+                        Some(&q) if q == c => break,
+                        // Only the quote and backslash are escapes, so regexes keep their `\d`
+                        Some('\\') if matches!(chars.get(i + 1), Some(&n) if n == c || n == '\\') => {
+                            text.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(&ch) => {
+                            text.push(ch);
+                            i += 1;
+                        }
+                    }
+                }
+                tokens.push((Token::Str(text), column));
+                i += 1;
+                continue;
+            }
+            c if c.is_ascii_digit() || (c == '-' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) => {
+                let start = i;
+                i += 1;
+                while chars.get(i).is_some_and(|d| d.is_ascii_digit() || *d == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let n = text.parse().map_err(|_| ExprError::new(format!("Invalid number '{text}'"), column))?;
+                tokens.push((Token::Num(n), column));
+                continue;
+            }
+            c if is_ident_start(c) => {
+                let start = i;
+                while chars.get(i).copied().is_some_and(is_ident_char) {
+                    i += 1;
+                }
+                tokens.push((Token::Ident(chars[start..i].iter().collect()), column));
+                continue;
+            }
+            _ => {
+                let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+                let op = OPERATORS
+                    .iter()
+                    .find(|op| rest.starts_with(**op))
+                    .ok_or_else(|| ExprError::new(format!("Unexpected character '{c}'"), column))?;
+                i += op.len();
+                tokens.push((Token::Op(op), column));
+                continue;
+            }
+        };
+        tokens.push((token, column));
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+// ── Parser (Pratt) ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Func {
+    Contains,
+    StartsWith,
+    EndsWith,
+    Matches,
+}
+
+impl Func {
+    fn lookup(name: &str) -> Option<Self> {
+        Some(match name {
+            "contains" => Func::Contains,
+            "startsWith" => Func::StartsWith,
+            "endsWith" => Func::EndsWith,
+            "matches" => Func::Matches,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Lit(Val),
+    Ref { path: Vec<String>, column: usize },
+    Not(Box<Expr>),
+    Binary { op: &'static str, lhs: Box<Expr>, rhs: Box<Expr>, column: usize },
+    Call { func: Func, args: Vec<Expr>, column: usize },
+}
+
+/// Left and right binding power of an infix operator.
+fn infix_power(op: &str) -> Option<(u8, u8)> {
+    Some(match op {
+        "||" => (1, 2),
+        "&&" => (3, 4),
+        "==" | "!=" => (5, 6),
+        "<" | "<=" | ">" | ">=" | "=~" => (7, 8),
+        _ => return None,
+    })
+}
+
+const PREFIX_POWER: u8 = 9;
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// Column just past the input, for errors at the end.
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |(_, c)| *c)
+    }
+
+    fn next(&mut self) -> Option<(Token, usize)> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<(), ExprError> {
+        let column = self.column();
+        match self.next() {
+            Some((token, _)) if token == expected => Ok(()),
+            _ => Err(ExprError::new(format!("Expected {what}"), column)),
+        }
+    }
+
+    fn expr(&mut self, min_power: u8) -> Result<Expr, ExprError> {
+        let column = self.column();
+        let mut lhs = match self.next() {
+            Some((Token::Num(n), _)) => Expr::Lit(Val::Num(n)),
+            Some((Token::Str(s), _)) => Expr::Lit(Val::Str(s)),
+            Some((Token::Op("!"), _)) => Expr::Not(Box::new(self.expr(PREFIX_POWER)?)),
+            Some((Token::LParen, _)) => {
+                let inner = self.expr(0)?;
+                self.expect(Token::RParen, "')'")?;
+                inner
+            }
+            Some((Token::Ident(name), _)) => match name.as_str() {
+                "true" => Expr::Lit(Val::Bool(true)),
+                "false" => Expr::Lit(Val::Bool(false)),
+                "null" => Expr::Lit(Val::Null),
+                _ if self.peek() == Some(&Token::LParen) => self.call(&name, column)?,
+                _ => {
+                    let path: Vec<String> = name.split('.').map(str::to_string).collect();
+                    check_path(&path).map_err(|e| ExprError::new(e, column))?;
+                    Expr::Ref { path, column }
+                }
+            },
+            Some((token, _)) => return Err(ExprError::new(format!("Unexpected {}", describe(&token)), column)),
+            None => return Err(ExprError::new("Expected a value", column)),
+        };
+
+        while let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            let Some((left, right)) = infix_power(op) else { break };
+            if left < min_power {
+                break;
+            }
+            let column = self.column();
+            self.pos += 1;
+            let rhs = self.expr(right)?;
+            if let ("=~", Expr::Lit(Val::Str(pattern))) = (op, &rhs) {
+                compile_regex(pattern, column)?;
+            }
+            lhs = Expr::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs), column };
+        }
+        Ok(lhs)
+    }
+
+    fn call(&mut self, name: &str, column: usize) -> Result<Expr, ExprError> {
+        let func = Func::lookup(name).ok_or_else(|| ExprError::new(format!("Unknown function '{name}'"), column))?;
+        self.expect(Token::LParen, "'('")?;
+        let mut args = Vec::new();
+        if self.peek() != Some(&Token::RParen) {
+            loop {
+                args.push(self.expr(0)?);
+                if self.peek() != Some(&Token::Comma) {
+                    break;
+                }
+                self.pos += 1;
+            }
+        }
+        self.expect(Token::RParen, "',' or ')'")?;
+        if args.len() != 2 {
+            return Err(ExprError::new(format!("{name}() takes 2 arguments, got {}", args.len()), column));
+        }
+        if let (Func::Matches, Expr::Lit(Val::Str(pattern))) = (func, &args[1]) {
+            compile_regex(pattern, column)?;
+        }
+        Ok(Expr::Call { func, args, column })
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Num(n) => format!("number {n}"),
+        Token::Str(s) => format!("string '{s}'"),
+        Token::Ident(name) => format!("'{name}'"),
+        Token::Op(op) => format!("'{op}'"),
+        Token::LParen => "'('".into(),
+        Token::RParen => "')'".into(),
+        Token::Comma => "','".into(),
+    }
+}
+
+/// Shape of a reference, checked at parse time so typos show in the editor.
+fn check_path(path: &[String]) -> Result<(), String> {
+    let path: Vec<&str> = path.iter().map(String::as_str).collect();
+    let field = match path[..] {
+        ["env", _] => return Ok(()),
+        ["env", ..] => return Err("Expected env.<NAME>".into()),
+        ["nodes", _, ref field @ ..] => field,
+        ["nodes", ..] => return Err("Expected nodes.<id>.<field>".into()),
+        ref field => field,
+    };
+    match field {
+        ["outputs", _] => Ok(()),
+        [name] if NODE_FIELDS.contains(name) => Ok(()),
+        _ => Err(format!(
+            "Unknown field '{}': expected one of {}, outputs.<name>",
+            field.join("."),
+            NODE_FIELDS.join(", ")
+        )),
+    }
+}
+
+fn compile_regex(pattern: &str, column: usize) -> Result<Regex, ExprError> {
+    Regex::new(pattern).map_err(|e| ExprError::new(format!("Invalid regex: {e}"), column))
+}
+
+// ── Evaluation ────────────────────────────────────────────────────────────────
+
+/// A parsed condition, ready to evaluate against run results.
+#[derive(Debug, Clone)]
+pub struct Expression {
+    root: Expr,
+}
+
+impl Expression {
+    pub fn parse(src: &str) -> Result<Self, ExprError> {
+        let end = src.chars().count() + 1;
+        let mut parser = Parser { tokens: tokenize(src)?, pos: 0, end };
+        if parser.tokens.is_empty() {
+            return Err(ExprError::new("Condition is empty", 1));
+        }
+        let root = parser.expr(0)?;
+        if let Some((token, column)) = parser.next() {
+            return Err(ExprError::new(format!("Unexpected {} after the expression", describe(&token)), column));
+        }
+        Ok(Self { root })
+    }
+
+    pub fn evaluate(&self, ctx: &ExprContext) -> Result<bool, ExprError> {
+        eval(&self.root, ctx).map(|v| v.truthy())
+    }
+}
+
+fn eval(expr: &Expr, ctx: &ExprContext) -> Result<Val, ExprError> {
+    match expr {
+        Expr::Lit(v) => Ok(v.clone()),
+        Expr::Ref { path, column } => resolve(path, ctx, *column),
+        Expr::Not(inner) => Ok(Val::Bool(!eval(inner, ctx)?.truthy())),
+        Expr::Binary { op: "&&", lhs, rhs, .. } => {
+            Ok(Val::Bool(eval(lhs, ctx)?.truthy() && eval(rhs, ctx)?.truthy()))
+        }
+        Expr::Binary { op: "||", lhs, rhs, .. } => {
+            Ok(Val::Bool(eval(lhs, ctx)?.truthy() || eval(rhs, ctx)?.truthy()))
+        }
+        Expr::Binary { op, lhs, rhs, column } => {
+            let (a, b) = (eval(lhs, ctx)?, eval(rhs, ctx)?);
+            Ok(Val::Bool(match *op {
+                "==" => equals(&a, &b),
+                "!=" => !equals(&a, &b),
+                "<" => compare(&a, &b, *column)?.is_lt(),
+                "<=" => compare(&a, &b, *column)?.is_le(),
+                ">" => compare(&a, &b, *column)?.is_gt(),
+                ">=" => compare(&a, &b, *column)?.is_ge(),
+                "=~" => regex_match(&a, &b, *column)?,
+                other => unreachable!("operator {other} is not infix"),
+            }))
+        }
+        Expr::Call { func, args, column } => {
+            let (a, b) = (eval(&args[0], ctx)?, eval(&args[1], ctx)?);
+            if *func == Func::Matches {
+                return regex_match(&a, &b, *column).map(Val::Bool);
+            }
+            let (Val::Str(text), Val::Str(part)) = (&a, &b) else {
+                return Err(ExprError::new(
+                    format!("Expected two strings, got {} and {}", a.type_name(), b.type_name()),
+                    *column,
+                ));
+            };
+            Ok(Val::Bool(match func {
+                Func::Contains => text.contains(part.as_str()),
+                Func::StartsWith => text.starts_with(part.as_str()),
+                Func::EndsWith => text.ends_with(part.as_str()),
+                Func::Matches => unreachable!(),
+            }))
+        }
+    }
+}
+
+fn regex_match(text: &Val, pattern: &Val, column: usize) -> Result<bool, ExprError> {
+    match (text, pattern) {
+        (Val::Null, Val::Str(_)) => Ok(false),
+        (Val::Str(text), Val::Str(pattern)) => Ok(compile_regex(pattern, column)?.is_match(text)),
+        _ => Err(ExprError::new(
+            format!("Regex match needs strings, got {} and {}", text.type_name(), pattern.type_name()),
+            column,
+        )),
+    }
+}
+
+/// Missing outputs and env vars read as `null`; unknown nodes are errors.
+fn resolve(path: &[String], ctx: &ExprContext, column: usize) -> Result<Val, ExprError> {
+    let (node, field) = match path {
+        [env, name] if env == "env" => return Ok(ctx.env.get(name).map_or(Val::Null, |v| Val::Str(v.clone()))),
+        [nodes, id, field @ ..] if nodes == "nodes" => (ctx.node(id, column)?, field),
+        field => (ctx.upstream_node(column)?, field),
+    };
+    Ok(match field {
+        [outputs, name] if outputs == "outputs" => node.outputs.get(name).map_or(Val::Null, |v| Val::Str(v.clone())),
+        [name] => match name.as_str() {
+            "status" => Val::Str(node.status.clone()),
+            "exit_code" => node.exit_code.map_or(Val::Null, |c| Val::Num(c.into())),
+            "duration_ms" => node.duration_ms.map_or(Val::Null, |d| Val::Num(d as f64)),
+            "timed_out" => Val::Bool(node.timed_out),
+            "stdout" => Val::Str(node.stdout.clone()),
+            "stderr" => Val::Str(node.stderr.clone()),
+            other => return Err(ExprError::new(format!("Unknown field '{other}'"), column)),
+        },
+        _ => return Err(ExprError::new(format!("Unknown field '{}'", field.join(".")), column)),
+    })
+}
+
+// ── Tauri commands ─────────────────────────────────────────────────────────────
+
+/// Check a condition's syntax. Returns the first error, or nothing when valid.
+#[tauri::command]
+pub fn validate_expression(expression: String) -> Option<ExprError> {
+    Expression::parse(&expression).err()
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> ExprContext {
+        let build = NodeFacts {
+            status: "success".into(),
+            exit_code: Some(0),
+            duration_ms: Some(1500),
+            stdout: "Built image\n".into(),
+            stderr: "warning: cache miss".into(),
+            outputs: Outputs::from([("tag".to_string(), "v1.4.2".to_string()), ("count".into(), "12".into())]),
+            ..Default::default()
+        };
+        let lint = NodeFacts { status: "error".into(), exit_code: Some(2), ..Default::default() };
+        ExprContext {
+            upstream: vec!["build".into()],
+            nodes: HashMap::from([("build".to_string(), build), ("lint-1".to_string(), lint)]),
+            env: HashMap::from([("CI".to_string(), "true".to_string())]),
+        }
+    }
+
+    fn eval_str(src: &str) -> Result<bool, ExprError> {
+        Expression::parse(src)?.evaluate(&ctx())
+    }
+
+    #[test]
+    fn test_comparisons_and_logic() {
+        assert!(eval_str("exit_code == 0").unwrap());
+        assert!(eval_str("exit_code == 0 && duration_ms < 2000").unwrap());
+        assert!(eval_str("nodes.lint-1.exit_code != 0 || false").unwrap());
+        assert!(eval_str("!(status == 'error') && env.CI == \"true\"").unwrap());
+        assert!(eval_str("outputs.count > 9 && outputs.count == 12").unwrap());
+        assert!(eval_str("env.MISSING_VAR_XYZ == null").unwrap());
+        assert!(!eval_str("true && false || false").unwrap());
+        // && binds tighter than ||
+        assert!(eval_str("true || false && false").unwrap());
+    }
+
+    #[test]
+    fn test_string_functions() {
+        assert!(eval_str("contains(stderr, 'cache miss')").unwrap());
+        assert!(eval_str("startsWith(outputs.tag, 'v1.') && endsWith(stdout, 'image\n')").unwrap());
+        assert!(eval_str(r"outputs.tag =~ '^v\d+\.\d+'").unwrap());
+        assert!(eval_str(r"matches(nodes.build.outputs.tag, '^v1')").unwrap());
+        assert!(!eval_str("outputs.nothing =~ 'x'").unwrap());
+    }
+
+    #[test]
+    fn test_parse_errors_have_columns() {
+        let err = Expression::parse("exit_code == ").unwrap_err();
+        assert_eq!(err.column, 14);
+        let err = Expression::parse("exit_code = 0").unwrap_err();
+        assert_eq!((err.column, err.message.as_str()), (11, "Unexpected character '='"));
+        let err = Expression::parse("exit_cod == 0").unwrap_err();
+        assert_eq!(err.column, 1);
+        assert!(err.message.contains("Unknown field"), "{}", err.message);
+        let err = Expression::parse("contains(stdout)").unwrap_err();
+        assert!(err.message.contains("takes 2 arguments"), "{}", err.message);
+        let err = Expression::parse("stdout =~ '('").unwrap_err();
+        assert!(err.message.starts_with("Invalid regex"), "{}", err.message);
+        assert_eq!(Expression::parse("(true").unwrap_err().column, 6);
+        assert_eq!(Expression::parse("true true").unwrap_err().column, 6);
+        assert_eq!(Expression::parse("'open").unwrap_err().message, "Unterminated string");
+    }
+
+    #[test]
+    fn test_evaluation_errors() {
+        let err = eval_str("nodes.deploy.exit_code == 0").unwrap_err();
+        assert!(err.message.contains("'deploy' has not run"), "{}", err.message);
+        let err = eval_str("stdout < 3").unwrap_err();
+        assert!(err.message.contains("Cannot compare"), "{}", err.message);
+
+        let mut ambiguous = ctx();
+        ambiguous.upstream.push("lint-1".into());
+        let err = Expression::parse("exit_code == 0").unwrap().evaluate(&ambiguous).unwrap_err();
+        assert!(err.message.contains("nodes.<id>"), "{}", err.message);
+    }
+}
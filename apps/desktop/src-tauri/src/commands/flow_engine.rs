@@ -0,0 +1,1640 @@
+// ============================================================
+// DevFlow Studio — Native Flow Engine (Rust)
+// Runs a whole FlowDefinition in the backend, so a flow keeps
+// going if the webview reloads and can run without the UI.
+// Topological batching with bounded parallelism. Each node's
+// `runWhen` decides whether it runs after its upstream nodes
+// succeeded, failed, or either (cleanup); false conditions
+// skip their dependents; resuming an earlier run carries over
+// the nodes that passed there, results and outputs included.
+// In step mode (the debugger) each node pauses before it
+// starts until `resume_node` releases it, one node at a time.
+// A node with a `matrix` runs one instance per combination,
+// aggregated onto the node; each instance takes one of the
+// run's maxParallel slots, at most the matrix's maxParallel
+// at a time.
+// ============================================================
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::{oneshot, watch, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinSet;
+
+use super::executor::{CommandResult, EnvValue, ExecRequest, ExecutionProfile, Program};
+use super::docker::{DockerConfig, VolumeMount};
+use super::expression::{ExprContext, Expression, NodeFacts};
+use super::sandbox::SandboxConfig;
+use super::shell::ShellSpec;
+use super::ssh::{SshConfig, SshMuxPool};
+use super::limits::ResourceLimits;
+use super::log_event::{now_rfc3339, LogEmitter, LogEvent, LogLevel};
+use super::matrix::{describe, Combination, MatrixPlan, MatrixSpec};
+use super::node_config::NodeConfig;
+use super::template::{render, RunOutputs};
+use super::process::{wait_finished, ProcessRegistry};
+use super::retry::{run_with_retry, RetryPolicy};
+
+/// Finished runs kept around for `get_run_status`.
+const MAX_FINISHED_RUNS: usize = 20;
+
+// ── Flow definition (mirrors packages/shared FlowDefinition) ──────────────────
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FlowNode {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub node_type: String,
+    pub label: String,
+    #[serde(default)]
+    pub config: Value,
+    #[serde(default)]
+    pub position: Position,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FlowEdge {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FlowDefinition {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub nodes: Vec<FlowNode>,
+    pub edges: Vec<FlowEdge>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+/// Options for a single `run_flow` invocation.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RunOptions {
+    /// Project directory used as the default working directory.
+    pub project_path: Option<String>,
+    /// Maximum number of nodes executing at once (default: CPU count).
+    pub max_parallel: Option<usize>,
+    /// A finished run to resume: nodes that passed there keep their result
+    /// and outputs instead of running again.
+    pub resume_from: Option<String>,
+    /// Nodes to run again on resume even though they passed.
+    #[serde(default)]
+    pub rerun: Vec<String>,
+    /// Pause before each node until `resume_node` (the debugger).
+    #[serde(default)]
+    pub step: bool,
+}
+
+/// Per-node `config.executionProfile`, as stored by the node settings panel.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct ExecutionSettings {
+    profile: Option<ExecutionProfile>,
+    timeout_seconds: Option<u64>,
+    docker_image: Option<String>,
+    /// Cores, for containers and native cgroups alike.
+    cpu_limit: Option<String>,
+    mem_limit: Option<String>,
+    pids_limit: Option<u64>,
+    io_weight: Option<u16>,
+    /// Comma-separated names/`PREFIX_*` patterns forwarded into the container.
+    docker_env_allowlist: Option<String>,
+    /// One `host:container[:ro]` mount per line.
+    docker_volumes: Option<String>,
+    docker_network: Option<String>,
+    docker_user: Option<String>,
+    docker_pull: Option<String>,
+    docker_entrypoint: Option<String>,
+    docker_shm_size: Option<String>,
+    /// `docker`, `podman`, or empty to auto-detect.
+    docker_runtime: Option<String>,
+    docker_selinux_label: Option<String>,
+    sandbox_network: Option<bool>,
+    /// Extra writable host paths, one per line.
+    sandbox_read_write: Option<String>,
+    ssh_host: Option<String>,
+    ssh_user: Option<String>,
+    ssh_port: Option<u16>,
+    ssh_identity_file: Option<String>,
+    ssh_identity_secret: Option<String>,
+    ssh_jump_host: Option<String>,
+    ssh_remote_cwd: Option<String>,
+    /// Comma-separated names/`PREFIX_*` patterns exported on the remote side.
+    ssh_env_allowlist: Option<String>,
+    ssh_connect_timeout: Option<u64>,
+}
+
+// ── Run state ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeStatus {
+    Idle,
+    Running,
+    Success,
+    /// Failed in a tolerated way: an allowed exit code, or `continueOnError`.
+    SuccessWithWarnings,
+    Error,
+    Skipped,
+    Cancelled,
+    /// Held by the debugger before it starts, until `resume_node`.
+    Paused,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RunState {
+    Running,
+    Success,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeRun {
+    pub status: NodeStatus,
+    pub result: Option<CommandResult>,
+    /// Why the node failed, or for `success_with_warnings` what was tolerated.
+    pub error: Option<String>,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    /// Per-instance outcomes of a matrix node, in expansion order.
+    pub matrix: Option<Vec<MatrixRun>>,
+    /// For a condition node that ran, whether its condition held. A false
+    /// condition succeeds but skips its dependents, on resume too.
+    pub condition_met: Option<bool>,
+}
+
+impl NodeRun {
+    fn idle() -> Self {
+        Self {
+            status: NodeStatus::Idle,
+            result: None,
+            error: None,
+            started_at: None,
+            finished_at: None,
+            matrix: None,
+            condition_met: None,
+        }
+    }
+}
+
+/// One instance of a matrix node.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MatrixRun {
+    pub id: String,
+    pub combination: Combination,
+    pub status: NodeStatus,
+    pub result: Option<CommandResult>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RunStatus {
+    pub run_id: String,
+    pub flow_id: String,
+    pub state: RunState,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub nodes: HashMap<String, NodeRun>,
+}
+
+/// Events streamed to the `run_flow` caller.
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", content = "data", rename_all = "camelCase")]
+pub enum FlowEvent {
+    Log(LogEvent),
+    NodeStatus(NodeStatusEvent),
+    RunFinished(RunFinishedEvent),
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeStatusEvent {
+    pub run_id: String,
+    pub node_id: String,
+    pub status: NodeStatus,
+    pub error: Option<String>,
+    /// Peak usage of the node's process tree, once it has a result.
+    pub max_cpu: Option<f32>,
+    pub max_memory_mb: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RunFinishedEvent {
+    pub run_id: String,
+    pub state: RunState,
+}
+
+/// One in-flight or finished flow run.
+struct FlowRun {
+    id: String,
+    status: Mutex<RunStatus>,
+    cancel: watch::Sender<bool>,
+    /// Set once the run has finished and its final state is recorded.
+    done: watch::Sender<bool>,
+    events: Channel<FlowEvent>,
+    /// The run's `maxParallel` slots, shared by nodes and matrix instances.
+    permits: Arc<Semaphore>,
+    /// Step mode: pause before each node.
+    step: AtomicBool,
+    /// The node held by the debugger, released by `resume_node`.
+    paused: Mutex<HashMap<String, oneshot::Sender<()>>>,
+    /// Only one node is paused at a time; the others queue here.
+    step_turn: tokio::sync::Mutex<()>,
+}
+
+/// All flow runs, managed as Tauri state.
+#[derive(Default)]
+pub struct FlowRuns {
+    next_id: AtomicU64,
+    runs: Mutex<HashMap<String, Arc<FlowRun>>>,
+}
+
+impl FlowRun {
+    fn new(
+        run_id: &str,
+        flow_id: &str,
+        nodes: HashMap<String, NodeRun>,
+        events: Channel<FlowEvent>,
+        max_parallel: usize,
+        step: bool,
+    ) -> Self {
+        Self {
+            id: run_id.to_string(),
+            status: Mutex::new(RunStatus {
+                run_id: run_id.to_string(),
+                flow_id: flow_id.to_string(),
+                state: RunState::Running,
+                started_at: now_rfc3339(),
+                finished_at: None,
+                nodes,
+            }),
+            cancel: watch::channel(false).0,
+            done: watch::channel(false).0,
+            events,
+            permits: Arc::new(Semaphore::new(max_parallel)),
+            step: AtomicBool::new(step),
+            paused: Mutex::new(HashMap::new()),
+            step_turn: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn snapshot(&self) -> RunStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    fn node_status(&self, node_id: &str) -> Option<NodeStatus> {
+        self.status.lock().unwrap().nodes.get(node_id).map(|n| n.status)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        *self.cancel.borrow()
+    }
+
+    /// Settle the run's final state once no node is executing any more: nodes
+    /// that never started are cancelled along with a cancelled run.
+    fn finish(&self) -> RunState {
+        let cancelled = self.is_cancelled();
+        if cancelled {
+            let idle: Vec<String> = self
+                .snapshot()
+                .nodes
+                .into_iter()
+                .filter(|(_, n)| n.status == NodeStatus::Idle)
+                .map(|(id, _)| id)
+                .collect();
+            for id in idle {
+                self.set_node(&id, NodeStatus::Cancelled, None, None);
+            }
+        }
+
+        let mut status = self.status.lock().unwrap();
+        status.state = if cancelled {
+            RunState::Cancelled
+        } else if status.nodes.values().any(|n| n.status == NodeStatus::Error) {
+            RunState::Failed
+        } else {
+            RunState::Success
+        };
+        status.finished_at = Some(now_rfc3339());
+        status.state
+    }
+
+    /// Resolves once `drive_run` has settled the run's final state.
+    async fn finished(&self) {
+        let mut rx = self.done.subscribe();
+        let _ = rx.wait_for(|d| *d).await;
+    }
+
+    /// Resolves once `cancel_run` has been called for this run.
+    async fn cancelled(&self) {
+        let mut rx = self.cancel.subscribe();
+        let _ = rx.wait_for(|c| *c).await;
+    }
+
+    fn set_node(&self, node_id: &str, status: NodeStatus, result: Option<CommandResult>, error: Option<String>) {
+        let peak = {
+            let mut run = self.status.lock().unwrap();
+            let Some(node) = run.nodes.get_mut(node_id) else { return };
+            match status {
+                NodeStatus::Running => node.started_at = Some(now_rfc3339()),
+                NodeStatus::Idle | NodeStatus::Paused => {}
+                _ => node.finished_at = Some(now_rfc3339()),
+            }
+            node.status = status;
+            node.error = error.clone();
+            if result.is_some() {
+                node.result = result;
+            }
+            node.result.as_ref().map(|r| (r.max_cpu, r.max_memory_mb))
+        };
+        let _ = self.events.send(FlowEvent::NodeStatus(NodeStatusEvent {
+            run_id: self.id.clone(),
+            node_id: node_id.to_string(),
+            status,
+            error,
+            max_cpu: peak.map(|(cpu, _)| cpu),
+            max_memory_mb: peak.map(|(_, mem)| mem),
+        }));
+    }
+
+    /// In step mode, hold a node before it starts until `resume_node` releases
+    /// it. Returns `false` if the run was cancelled meanwhile.
+    async fn pause_before(&self, node_id: &str) -> bool {
+        if !self.step.load(Ordering::SeqCst) {
+            return true;
+        }
+        let _turn = self.step_turn.lock().await;
+        // Stepping may have been turned off while this node queued
+        if !self.step.load(Ordering::SeqCst) || self.is_cancelled() {
+            return !self.is_cancelled();
+        }
+        let (tx, rx) = oneshot::channel();
+        self.paused.lock().unwrap().insert(node_id.to_string(), tx);
+        self.set_node(node_id, NodeStatus::Paused, None, None);
+        tokio::select! {
+            _ = rx => true,
+            _ = self.cancelled() => {
+                self.paused.lock().unwrap().remove(node_id);
+                false
+            }
+        }
+    }
+
+    fn resume(&self, node_id: &str) -> bool {
+        match self.paused.lock().unwrap().remove(node_id) {
+            Some(tx) => tx.send(()).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Turn step mode on or off; turning it off releases the paused node.
+    fn set_step(&self, step: bool) {
+        self.step.store(step, Ordering::SeqCst);
+        if !step {
+            for (_, tx) in self.paused.lock().unwrap().drain() {
+                let _ = tx.send(());
+            }
+        }
+    }
+
+    fn set_condition(&self, node_id: &str, met: bool) {
+        if let Some(node) = self.status.lock().unwrap().nodes.get_mut(node_id) {
+            node.condition_met = Some(met);
+        }
+    }
+
+    /// Nodes whose dependents may proceed: passed, unless a false condition.
+    fn passed(&self) -> HashSet<String> {
+        let run = self.status.lock().unwrap();
+        run.nodes
+            .iter()
+            .filter(|(_, n)| matches!(n.status, NodeStatus::Success | NodeStatus::SuccessWithWarnings))
+            .filter(|(_, n)| n.condition_met != Some(false))
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    fn set_matrix(&self, node_id: &str, instances: Vec<MatrixRun>) {
+        if let Some(node) = self.status.lock().unwrap().nodes.get_mut(node_id) {
+            node.matrix = Some(instances);
+        }
+    }
+
+    fn set_instance(&self, node_id: &str, index: usize, status: NodeStatus, outcome: Option<Outcome>) {
+        let mut run = self.status.lock().unwrap();
+        let Some(instance) = run.nodes.get_mut(node_id).and_then(|n| n.matrix.as_mut()).and_then(|m| m.get_mut(index))
+        else {
+            return;
+        };
+        instance.status = status;
+        if let Some(outcome) = outcome {
+            instance.result = outcome.result;
+            instance.error = outcome.error;
+        }
+    }
+
+    /// Outputs published so far, for `${{ nodes.<id>.outputs.<name> }}`.
+    fn outputs(&self) -> RunOutputs {
+        let run = self.status.lock().unwrap();
+        run.nodes
+            .iter()
+            .filter_map(|(id, node)| node.result.as_ref().map(|r| (id.clone(), r.outputs.clone())))
+            .collect()
+    }
+
+    /// What a condition can see: every finished node, plus the host env.
+    fn expr_context(&self, upstream: Vec<String>) -> ExprContext {
+        let run = self.status.lock().unwrap();
+        let nodes = run
+            .nodes
+            .iter()
+            .filter(|(_, node)| !matches!(node.status, NodeStatus::Idle | NodeStatus::Running | NodeStatus::Paused))
+            .map(|(id, node)| {
+                let status = serde_json::to_value(node.status).ok().and_then(|v| v.as_str().map(str::to_string));
+                let facts = match &node.result {
+                    Some(r) => NodeFacts {
+                        exit_code: Some(r.exit_code),
+                        duration_ms: Some(r.duration_ms),
+                        timed_out: r.timed_out,
+                        stdout: r.stdout.clone(),
+                        stderr: r.stderr.clone(),
+                        outputs: r.outputs.clone(),
+                        ..Default::default()
+                    },
+                    None => NodeFacts::default(),
+                };
+                (id.clone(), NodeFacts { status: status.unwrap_or_default(), ..facts })
+            })
+            .collect();
+        ExprContext { upstream, nodes, env: HashMap::new() }.with_host_env()
+    }
+
+    fn log_emitter(&self, node_id: &str) -> LogEmitter {
+        let events = self.events.clone();
+        LogEmitter::with_sink(move |event| { let _ = events.send(FlowEvent::Log(event)); }, node_id, Some(&self.id))
+    }
+}
+
+// ── Scheduling ────────────────────────────────────────────────────────────────
+
+/// Group nodes into topological batches (Kahn's algorithm, layer by layer).
+/// Nodes in the same batch have no dependencies on each other.
+/// Edges that reference unknown nodes are ignored.
+pub fn plan_batches(nodes: &[FlowNode], edges: &[FlowEdge]) -> Result<Vec<Vec<String>>, String> {
+    let ids: HashSet<&str> = nodes.iter().map(|n| n.id.as_str()).collect();
+    let mut in_degree: HashMap<&str, usize> = nodes.iter().map(|n| (n.id.as_str(), 0)).collect();
+    let mut downstream: HashMap<&str, Vec<&str>> = HashMap::new();
+
+    for edge in edges {
+        if ids.contains(edge.source.as_str()) && ids.contains(edge.target.as_str()) {
+            downstream.entry(edge.source.as_str()).or_default().push(edge.target.as_str());
+            *in_degree.get_mut(edge.target.as_str()).unwrap() += 1;
+        }
+    }
+
+    let mut batch: Vec<&str> = nodes
+        .iter()
+        .map(|n| n.id.as_str())
+        .filter(|id| in_degree[id] == 0)
+        .collect();
+    let mut batches = Vec::new();
+    let mut planned = 0;
+
+    while !batch.is_empty() {
+        planned += batch.len();
+        let mut next = Vec::new();
+        for id in &batch {
+            for target in downstream.get(id).into_iter().flatten() {
+                let deg = in_degree.get_mut(target).unwrap();
+                *deg -= 1;
+                if *deg == 0 {
+                    next.push(*target);
+                }
+            }
+        }
+        batches.push(batch.iter().map(|s| s.to_string()).collect());
+        batch = next;
+    }
+
+    if planned != nodes.len() {
+        return Err("Flow contains a cycle; cannot determine execution order".into());
+    }
+    Ok(batches)
+}
+
+/// Every node `start` depends on, directly or transitively.
+fn ancestors_of(start: &str, edges: &[FlowEdge]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([start.to_string()]);
+    while let Some(id) = queue.pop_front() {
+        for edge in edges.iter().filter(|e| e.target == id) {
+            if seen.insert(edge.source.clone()) {
+                queue.push_back(edge.source.clone());
+            }
+        }
+    }
+    seen.into_iter().collect()
+}
+
+/// Nodes with an edge into `target`.
+fn upstream_of(target: &str, edges: &[FlowEdge]) -> Vec<String> {
+    edges.iter().filter(|e| e.target == target).map(|e| e.source.clone()).collect()
+}
+
+/// When a node runs, relative to the nodes before it.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunWhen {
+    /// Every upstream node succeeded (the default).
+    #[default]
+    OnSuccess,
+    /// Some node this one depends on failed, e.g. to roll back.
+    OnFailure,
+    /// Once upstream nodes are done, whatever their outcome: cleanup steps.
+    Always,
+}
+
+/// Whether a node runs now that everything upstream has settled. `passed`
+/// holds the nodes whose dependents may proceed: succeeded (with or without
+/// warnings) and, for conditions, evaluated true.
+fn should_run(
+    node_id: &str,
+    run_when: RunWhen,
+    edges: &[FlowEdge],
+    passed: &HashSet<String>,
+    status: impl Fn(&str) -> Option<NodeStatus>,
+) -> bool {
+    match run_when {
+        RunWhen::OnSuccess => upstream_of(node_id, edges).iter().all(|id| passed.contains(id)),
+        RunWhen::OnFailure => ancestors_of(node_id, edges).iter().any(|id| status(id) == Some(NodeStatus::Error)),
+        RunWhen::Always => true,
+    }
+}
+
+// ── Node commands ─────────────────────────────────────────────────────────────
+
+/// String config value; empty strings count as unset (like `||` in the UI).
+fn cfg_str(cfg: &Value, key: &str) -> Option<String> {
+    match cfg.get(key)? {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// What a node runs: verbatim scripts go through the shell, built-in
+/// command nodes are spawned from their typed argv.
+fn node_program(node: &FlowNode, project_path: Option<&str>) -> Result<Option<Program>, String> {
+    if node.node_type == "scriptRun" {
+        return Ok(cfg_str(&node.config, "command").map(Program::Shell));
+    }
+    match NodeConfig::from_node(&node.node_type, &node.config)? {
+        Some(config) => config.argv(project_path).map(|argv| Some(Program::Argv(argv))),
+        None => Ok(None),
+    }
+}
+
+/// Env value from node config. Flows saved before secret references existed
+/// store `$SECRET_<KEY>` strings; those become references too.
+fn env_value(value: &Value) -> Option<EnvValue> {
+    match value {
+        Value::String(s) => Some(match s.strip_prefix("$SECRET_") {
+            Some(key) => EnvValue::Secret { secret: key.to_string() },
+            None => EnvValue::Plain(s.clone()),
+        }),
+        other => serde_json::from_value(other.clone()).ok(),
+    }
+}
+
+/// Text inputs left empty in the settings panel are stored as `""`.
+fn set(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// A select field's value as one of a fixed set of serde variants.
+fn choice<T: DeserializeOwned>(value: &Option<String>, what: &str) -> Result<Option<T>, String> {
+    set(value.clone())
+        .map(|v| serde_json::from_value(Value::String(v.clone())).map_err(|_| format!("Invalid {what} '{v}'")))
+        .transpose()
+}
+
+impl ExecutionSettings {
+    fn docker_config(&self) -> Result<DockerConfig, String> {
+        let volumes = set(self.docker_volumes.clone())
+            .map(|text| text.lines().filter(|l| !l.trim().is_empty()).map(VolumeMount::parse).collect())
+            .transpose()?
+            .unwrap_or_default();
+        Ok(DockerConfig {
+            runtime: choice(&self.docker_runtime, "container runtime")?,
+            image: set(self.docker_image.clone()),
+            cpu_limit: set(self.cpu_limit.clone()),
+            mem_limit: set(self.mem_limit.clone()),
+            env_allowlist: set(self.docker_env_allowlist.clone()).map(|list| comma_list(&list)),
+            volumes,
+            network: set(self.docker_network.clone()),
+            user: set(self.docker_user.clone()),
+            pull: choice(&self.docker_pull, "pull policy")?,
+            entrypoint: set(self.docker_entrypoint.clone()),
+            shm_size: set(self.docker_shm_size.clone()),
+            selinux_label: choice(&self.docker_selinux_label, "SELinux label")?,
+        })
+    }
+
+    fn resource_limits(&self) -> Result<ResourceLimits, String> {
+        let cpu = set(self.cpu_limit.clone())
+            .map(|c| c.parse::<f64>().map_err(|_| format!("Invalid CPU limit '{c}'")))
+            .transpose()?;
+        Ok(ResourceLimits { cpu, memory: set(self.mem_limit.clone()), pids: self.pids_limit, io_weight: self.io_weight })
+    }
+
+    fn sandbox_config(&self) -> SandboxConfig {
+        SandboxConfig {
+            allow_network: self.sandbox_network.unwrap_or(false),
+            read_write: set(self.sandbox_read_write.clone())
+                .map(|text| text.lines().map(str::trim).filter(|l| !l.is_empty()).map(str::to_string).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn ssh_config(&self) -> SshConfig {
+        SshConfig {
+            host: set(self.ssh_host.clone()),
+            user: set(self.ssh_user.clone()),
+            port: self.ssh_port,
+            identity_file: set(self.ssh_identity_file.clone()),
+            identity_secret: set(self.ssh_identity_secret.clone()),
+            jump_host: set(self.ssh_jump_host.clone()),
+            remote_cwd: set(self.ssh_remote_cwd.clone()),
+            env_allowlist: set(self.ssh_env_allowlist.clone()).map(|list| comma_list(&list)),
+            connect_timeout_secs: self.ssh_connect_timeout,
+        }
+    }
+}
+
+fn comma_list(list: &str) -> Vec<String> {
+    list.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()
+}
+
+/// Build the executor request for a command-running node.
+fn exec_request(node: &FlowNode, run_id: &str, program: Program, project_path: Option<&str>) -> Result<ExecRequest, String> {
+    let cfg = &node.config;
+    // A profile that fails to parse must not silently fall back to running natively
+    let settings: ExecutionSettings = match cfg.get("executionProfile") {
+        Some(v) if !v.is_null() => serde_json::from_value(v.clone())
+            .map_err(|e| format!("Invalid execution profile for node {}: {e}", node.id))?,
+        _ => ExecutionSettings::default(),
+    };
+
+    let env_vars = match cfg.get("envVars") {
+        Some(Value::Object(map)) => map
+            .iter()
+            .filter(|(k, _)| !k.is_empty())
+            .filter_map(|(k, v)| env_value(v).map(|v| (k.clone(), v)))
+            .collect(),
+        _ => HashMap::new(),
+    };
+
+    let shell = match cfg.get("shell") {
+        Some(spec) => ShellSpec::deserialize(spec).map_err(|e| format!("Invalid shell: {e}"))?,
+        None => ShellSpec::Auto,
+    };
+
+    Ok(ExecRequest {
+        node_id: node.id.clone(),
+        run_id: Some(run_id.to_string()),
+        program,
+        shell,
+        cwd: project_path.map(str::to_string),
+        env_vars,
+        timeout_seconds: settings.timeout_seconds,
+        profile: settings.profile.clone().unwrap_or_default(),
+        docker_config: settings.docker_config()?,
+        ssh_config: settings.ssh_config(),
+        sandbox_config: settings.sandbox_config(),
+        limits: settings.resource_limits()?,
+        kill_grace_seconds: None,
+    })
+}
+
+/// The node's `config.retryPolicy`; nodes without one run once.
+fn retry_policy(cfg: &Value) -> Result<RetryPolicy, String> {
+    match cfg.get("retryPolicy") {
+        Some(policy) if !policy.is_null() => {
+            RetryPolicy::deserialize(policy).map_err(|e| format!("Invalid retry policy: {e}"))
+        }
+        _ => Ok(RetryPolicy::default()),
+    }
+}
+
+/// How a node's failure is treated, from `allowedExitCodes`, `continueOnError`
+/// and `runWhen` in its config.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct FailurePolicy {
+    /// Non-zero exit codes that still count as success, with warnings.
+    allowed_exit_codes: Vec<i32>,
+    /// A failure is reported as `success_with_warnings` and dependents run.
+    continue_on_error: bool,
+    run_when: RunWhen,
+}
+
+impl FailurePolicy {
+    fn from_config(cfg: &Value) -> Result<Self, String> {
+        let allowed_exit_codes = match cfg.get("allowedExitCodes") {
+            Some(Value::Array(codes)) => codes
+                .iter()
+                .map(|c| c.as_i64().and_then(|c| i32::try_from(c).ok()).ok_or(format!("Invalid allowed exit code {c}")))
+                .collect::<Result<_, _>>()?,
+            // Typed into the settings panel as "1, 3"
+            Some(Value::String(codes)) => comma_list(codes)
+                .iter()
+                .map(|c| c.parse().map_err(|_| format!("Invalid allowed exit code '{c}'")))
+                .collect::<Result<_, _>>()?,
+            _ => Vec::new(),
+        };
+        Ok(Self {
+            allowed_exit_codes,
+            continue_on_error: cfg.get("continueOnError").and_then(Value::as_bool).unwrap_or(false),
+            run_when: choice(&cfg_str(cfg, "runWhen"), "run condition")?.unwrap_or_default(),
+        })
+    }
+}
+
+/// What running a node, or one matrix instance, came to.
+#[derive(Debug)]
+struct Outcome {
+    status: NodeStatus,
+    error: Option<String>,
+    result: Option<CommandResult>,
+    /// False for a condition that evaluated false.
+    proceed: bool,
+}
+
+impl Outcome {
+    fn failed(error: String) -> Self {
+        Self { status: NodeStatus::Error, error: Some(error), result: None, proceed: true }
+    }
+}
+
+/// Run a single node to completion and record its outcome. Returns `true` when
+/// its dependents may proceed: on success (with or without warnings), unless it
+/// is a condition that was false.
+#[allow(clippy::too_many_arguments)]
+async fn execute_node(
+    app: AppHandle,
+    run: Arc<FlowRun>,
+    node: FlowNode,
+    upstream: Vec<String>,
+    policy: FailurePolicy,
+    project_path: Option<String>,
+    permit: OwnedSemaphorePermit,
+) -> bool {
+    run.set_node(&node.id, NodeStatus::Running, None, None);
+    let log = run.log_emitter(&node.id);
+    log.system(LogLevel::Info, format!("Starting {}...", node.label));
+
+    let outcome = match MatrixSpec::from_config(&node.config) {
+        Ok(Some(spec)) => {
+            // Instances take their own slots; holding this one too could starve them
+            drop(permit);
+            run_matrix(&app, &run, &node, &spec, &upstream, &policy, project_path, &log).await
+        }
+        // Upstream nodes have finished, so their outputs are final
+        Ok(None) => match render(&node.config, &run.outputs()) {
+            Ok(config) => {
+                let node = FlowNode { config, ..node.clone() };
+                run_once(&app, &run, &node, upstream, &policy, project_path.as_deref(), &log).await
+            }
+            Err(e) => Outcome::failed(e),
+        },
+        Err(e) => Outcome::failed(e),
+    };
+    let Outcome { status, error, result, proceed } = outcome;
+
+    let status = match (status, &error) {
+        (NodeStatus::Error, Some(e)) if policy.continue_on_error => {
+            log.system(LogLevel::Warn, format!("{} failed: {e} — continuing (continue on error)", node.label));
+            NodeStatus::SuccessWithWarnings
+        }
+        (NodeStatus::Error, Some(e)) => {
+            log.system(LogLevel::Error, format!("{} failed: {e}", node.label));
+            status
+        }
+        (NodeStatus::SuccessWithWarnings, Some(e)) => {
+            log.system(LogLevel::Warn, format!("{} finished with warnings: {e}", node.label));
+            status
+        }
+        _ => status,
+    };
+    if node.node_type == "conditionalNode" && status == NodeStatus::Success {
+        run.set_condition(&node.id, proceed);
+    }
+    run.set_node(&node.id, status, result, error);
+    matches!(status, NodeStatus::Success | NodeStatus::SuccessWithWarnings) && proceed
+}
+
+/// Run a node's work once with its rendered config. `continueOnError` is
+/// applied by the caller, so matrix instances fail fast regardless.
+async fn run_once(
+    app: &AppHandle,
+    run: &Arc<FlowRun>,
+    node: &FlowNode,
+    upstream: Vec<String>,
+    policy: &FailurePolicy,
+    project_path: Option<&str>,
+    log: &LogEmitter,
+) -> Outcome {
+    let mut proceed = true;
+    let outcome: Result<Option<CommandResult>, String> = match node.node_type.as_str() {
+        "delayNode" => {
+            let secs = cfg_str(&node.config, "seconds").and_then(|s| s.parse::<f64>().ok()).unwrap_or(5.0);
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs_f64(secs.max(0.0))) => Ok(None),
+                _ = run.cancelled() => Err("Cancelled".into()),
+            }
+        }
+        "notification" => {
+            let title = cfg_str(&node.config, "title").unwrap_or_default();
+            let message = cfg_str(&node.config, "message").unwrap_or_default();
+            log.system(LogLevel::Info, format!("🔔 {title}: {message}"));
+            Ok(None)
+        }
+        "conditionalNode" => {
+            let condition = cfg_str(&node.config, "condition").unwrap_or_default();
+            match Expression::parse(&condition).and_then(|expr| expr.evaluate(&run.expr_context(upstream))) {
+                Ok(met) => {
+                    let branch = if met { "running" } else { "skipping" };
+                    log.system(LogLevel::Info, format!("Condition `{condition}` is {met}: {branch} downstream nodes"));
+                    proceed = met;
+                    Ok(None)
+                }
+                Err(e) => Err(format!("Invalid condition: {e}")),
+            }
+        }
+        // Pass-through node: it only shapes the graph
+        "parallelGroup" => Ok(None),
+        other => match node_program(node, project_path) {
+            Ok(Some(program)) => match exec_request(node, &run.id, program, project_path) {
+                Ok(request) => match retry_policy(&node.config) {
+                    // Last check before spawning: rendering and setup take time too
+                    Ok(_) if run.is_cancelled() => Err("Cancelled".into()),
+                    Ok(retry) => {
+                        run_with_retry(app, request, &retry, &policy.allowed_exit_codes, log.clone()).await.map(Some)
+                    }
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            },
+            Ok(None) => Err(format!("Node type '{other}' cannot run in the native engine")),
+            Err(e) => Err(e),
+        },
+    };
+
+    let (status, error, result) = match outcome {
+        Ok(None) => (NodeStatus::Success, None, None),
+        // A run cancelled while waiting to retry keeps the failed attempt's result
+        Ok(Some(result)) if result.cancelled || run.is_cancelled() => (NodeStatus::Cancelled, None, Some(result)),
+        Ok(Some(result)) if result.termination.is_success() => (NodeStatus::Success, None, Some(result)),
+        Ok(Some(result)) if result.termination.allows(&policy.allowed_exit_codes) => {
+            (NodeStatus::SuccessWithWarnings, Some(format!("{} (allowed)", result.termination)), Some(result))
+        }
+        Ok(Some(result)) => (NodeStatus::Error, Some(result.termination.to_string()), Some(result)),
+        Err(_) if run.is_cancelled() => (NodeStatus::Cancelled, None, None),
+        Err(e) => (NodeStatus::Error, Some(e), None),
+    };
+    Outcome { status, error, result, proceed }
+}
+
+/// Run every instance of a matrix node and aggregate them: an error if any
+/// instance failed, with warnings if any had warnings, and a result built by
+/// `aggregate_matrix`. With `failFast`, the first failure cancels running
+/// instances and skips those not yet started.
+#[allow(clippy::too_many_arguments)]
+async fn run_matrix(
+    app: &AppHandle,
+    run: &Arc<FlowRun>,
+    node: &FlowNode,
+    spec: &MatrixSpec,
+    upstream: &[String],
+    policy: &FailurePolicy,
+    project_path: Option<String>,
+    log: &LogEmitter,
+) -> Outcome {
+    let plan = match MatrixPlan::new(&node.id, &node.config, spec) {
+        Ok(plan) => plan,
+        Err(e) => return Outcome::failed(e),
+    };
+    let started = std::time::Instant::now();
+    let total = plan.instances.len();
+    log.system(LogLevel::Info, format!("{} expands into {total} matrix instances", node.label));
+    run.set_matrix(
+        &node.id,
+        plan.instances
+            .iter()
+            .map(|i| MatrixRun {
+                id: i.id.clone(),
+                combination: i.combination.clone(),
+                status: NodeStatus::Idle,
+                result: None,
+                error: None,
+            })
+            .collect(),
+    );
+
+    let slots = Arc::new(Semaphore::new(plan.max_parallel.unwrap_or(total).max(1)));
+    let (stop, stopped) = watch::channel(false);
+    let ids: Vec<String> = plan.instances.iter().map(|i| i.id.clone()).collect();
+    let combinations: Vec<Combination> = plan.instances.iter().map(|i| i.combination.clone()).collect();
+    let mut tasks = JoinSet::new();
+    for instance in plan.instances {
+        let label = format!("{} [{}]", node.label, describe(&instance.combination));
+        let instance_node = FlowNode { id: instance.id, label, config: instance.config, ..node.clone() };
+        let (app, run, slots, stopped, log) = (app.clone(), run.clone(), slots.clone(), stopped.clone(), log.clone());
+        let (parent, upstream, policy, project_path) =
+            (node.id.clone(), upstream.to_vec(), policy.clone(), project_path.clone());
+        tasks.spawn(async move {
+            // The matrix's cap first, so waiting on it doesn't hold a run slot
+            let _slot = slots.acquire_owned().await;
+            let _permit = run.permits.clone().acquire_owned().await;
+            if run.is_cancelled() {
+                return (instance.index, NodeStatus::Cancelled, None);
+            }
+            if *stopped.borrow() {
+                return (instance.index, NodeStatus::Skipped, None);
+            }
+            run.set_instance(&parent, instance.index, NodeStatus::Running, None);
+            log.system(LogLevel::Info, format!("Starting {}...", instance_node.label));
+            let outcome = match render(&instance_node.config, &run.outputs()) {
+                Ok(config) => {
+                    let instance_node = FlowNode { config, ..instance_node.clone() };
+                    run_once(&app, &run, &instance_node, upstream, &policy, project_path.as_deref(), &log).await
+                }
+                Err(e) => Outcome::failed(e),
+            };
+            match (outcome.status, &outcome.error) {
+                (NodeStatus::Error, Some(e)) => log.system(LogLevel::Error, format!("{} failed: {e}", instance_node.label)),
+                (NodeStatus::SuccessWithWarnings, Some(e)) => {
+                    log.system(LogLevel::Warn, format!("{} finished with warnings: {e}", instance_node.label))
+                }
+                (NodeStatus::Success, _) => log.system(LogLevel::Info, format!("{} succeeded", instance_node.label)),
+                _ => {}
+            }
+            (instance.index, outcome.status, Some(outcome))
+        });
+    }
+
+    let mut statuses = vec![NodeStatus::Idle; total];
+    let mut results: Vec<Option<CommandResult>> = vec![None; total];
+    while let Some(joined) = tasks.join_next().await {
+        let Ok((index, status, outcome)) = joined else { continue };
+        statuses[index] = status;
+        results[index] = outcome.as_ref().and_then(|o| o.result.clone());
+        run.set_instance(&node.id, index, status, outcome);
+        if status == NodeStatus::Error && spec.fail_fast && !stop.send_replace(true) {
+            log.system(LogLevel::Warn, format!("{}: fail-fast, cancelling the remaining instances", node.label));
+            let registry = app.state::<ProcessRegistry>();
+            for id in &ids {
+                registry.cancel(Some(id), Some(&run.id));
+            }
+        }
+    }
+
+    let failed: Vec<String> = statuses
+        .iter()
+        .zip(&combinations)
+        .filter(|(status, _)| **status == NodeStatus::Error)
+        .map(|(_, combination)| describe(combination))
+        .collect();
+    let count = |s: NodeStatus| statuses.iter().filter(|&&st| st == s).count();
+    let (status, error) = if !failed.is_empty() {
+        (NodeStatus::Error, Some(format!("{} of {total} matrix instances failed: {}", failed.len(), failed.join("; "))))
+    } else if count(NodeStatus::Cancelled) > 0 {
+        (NodeStatus::Cancelled, None)
+    } else if count(NodeStatus::SuccessWithWarnings) > 0 {
+        let warned = count(NodeStatus::SuccessWithWarnings);
+        (NodeStatus::SuccessWithWarnings, Some(format!("{warned} of {total} matrix instances finished with warnings")))
+    } else {
+        (NodeStatus::Success, None)
+    };
+    let instances: Vec<(NodeStatus, Option<CommandResult>)> = statuses.into_iter().zip(results).collect();
+    let result = aggregate_matrix(&instances, started.elapsed().as_millis() as u64).map(|(result, conflicts)| {
+        if !conflicts.is_empty() {
+            let names = conflicts.join(", ");
+            let message = format!("{}: instances published different values for {names}; keeping the last", node.label);
+            log.system(LogLevel::Warn, message);
+        }
+        result
+    });
+    Outcome { status, error, result, proceed: true }
+}
+
+/// The parent node's result for a matrix, from its instances' statuses and
+/// results in expansion order. `None` if no instance produced a result.
+/// - Exit code, termination and the timed-out and cancelled flags come from
+///   the first failed instance, else the first cancelled one, else the first
+///   with warnings, else the first.
+/// - `duration_ms` is the matrix's wall time. Peak CPU, memory and threads
+///   are the highest of any instance; disk I/O is the total.
+/// - Outputs are merged in expansion order, so where instances disagree the
+///   last one's value wins. The names they disagreed on are returned too.
+/// - Streams, samples and attempts stay on each instance's `MatrixRun`.
+fn aggregate_matrix(instances: &[(NodeStatus, Option<CommandResult>)], duration_ms: u64) -> Option<(CommandResult, Vec<String>)> {
+    let ran = || instances.iter().filter_map(|(status, result)| result.as_ref().map(|r| (*status, r)));
+    let (_, decisive) = [NodeStatus::Error, NodeStatus::Cancelled, NodeStatus::SuccessWithWarnings]
+        .into_iter()
+        .find_map(|wanted| ran().find(|(status, _)| *status == wanted))
+        .or_else(|| ran().next())?;
+
+    let mut result = CommandResult {
+        stdout: String::new(),
+        stderr: String::new(),
+        log_file_path: None,
+        samples: Vec::new(),
+        attempts: Vec::new(),
+        outputs: Default::default(),
+        duration_ms,
+        truncated: ran().any(|(_, r)| r.truncated),
+        max_cpu: ran().map(|(_, r)| r.max_cpu).fold(0.0, f32::max),
+        max_memory_mb: ran().map(|(_, r)| r.max_memory_mb).max().unwrap_or(0),
+        max_threads: ran().map(|(_, r)| r.max_threads).max().unwrap_or(0),
+        disk_read_bytes: ran().map(|(_, r)| r.disk_read_bytes).sum(),
+        disk_written_bytes: ran().map(|(_, r)| r.disk_written_bytes).sum(),
+        ..decisive.clone()
+    };
+    let mut conflicts = Vec::new();
+    for (_, r) in ran() {
+        for (name, value) in &r.outputs {
+            let previous = result.outputs.insert(name.clone(), value.clone());
+            if previous.is_some_and(|p| p != *value) && !conflicts.contains(name) {
+                conflicts.push(name.clone());
+            }
+        }
+    }
+    Some((result, conflicts))
+}
+
+/// Drive a run batch by batch until every node has finished, failed, or been skipped.
+async fn drive_run(app: AppHandle, run: Arc<FlowRun>, flow: FlowDefinition, batches: Vec<Vec<String>>, options: RunOptions) {
+    let nodes: HashMap<String, FlowNode> = flow.nodes.iter().map(|n| (n.id.clone(), n.clone())).collect();
+    // Carried over from the run being resumed
+    let mut passed = run.passed();
+
+    for batch in batches {
+        if run.is_cancelled() {
+            break;
+        }
+
+        let mut tasks = JoinSet::new();
+        for node_id in batch {
+            // Carried over (resume)
+            if run.node_status(&node_id) != Some(NodeStatus::Idle) {
+                continue;
+            }
+            let node = nodes[&node_id].clone();
+            let policy = match FailurePolicy::from_config(&node.config) {
+                Ok(policy) => policy,
+                Err(e) => {
+                    run.set_node(&node_id, NodeStatus::Error, None, Some(e));
+                    continue;
+                }
+            };
+            if !should_run(&node_id, policy.run_when, &flow.edges, &passed, |id| run.node_status(id)) {
+                run.set_node(&node_id, NodeStatus::Skipped, None, None);
+                continue;
+            }
+            let upstream = upstream_of(&node_id, &flow.edges);
+            let (app, run, project_path) = (app.clone(), run.clone(), options.project_path.clone());
+            tasks.spawn(async move {
+                if !run.pause_before(&node_id).await {
+                    run.set_node(&node_id, NodeStatus::Cancelled, None, None);
+                    return (node_id, false);
+                }
+                let permit = run.permits.clone().acquire_owned().await;
+                // cancel_run may have landed while this node waited for a permit
+                if run.is_cancelled() {
+                    run.set_node(&node_id, NodeStatus::Cancelled, None, None);
+                    return (node_id, false);
+                }
+                let Ok(permit) = permit else { return (node_id, false) };
+                let ok = execute_node(app, run, node, upstream, policy, project_path, permit).await;
+                (node_id, ok)
+            });
+        }
+
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok((node_id, true)) = joined {
+                passed.insert(node_id);
+            }
+        }
+    }
+
+    let state = run.finish();
+    app.state::<SshMuxPool>().close_run(&run.id).await;
+    let _ = run.events.send(FlowEvent::RunFinished(RunFinishedEvent { run_id: run.id.clone(), state }));
+    run.done.send_replace(true);
+}
+
+/// A run's nodes before it starts: idle, except that on resume every node
+/// that passed in `prior` keeps its result, and so its outputs, unless listed
+/// in `rerun`.
+fn initial_nodes(flow: &FlowDefinition, prior: Option<&RunStatus>, rerun: &[String]) -> HashMap<String, NodeRun> {
+    flow.nodes
+        .iter()
+        .map(|n| {
+            let carried = prior
+                .and_then(|p| p.nodes.get(&n.id))
+                .filter(|p| matches!(p.status, NodeStatus::Success | NodeStatus::SuccessWithWarnings))
+                .filter(|_| !rerun.contains(&n.id));
+            (n.id.clone(), carried.cloned().unwrap_or_else(NodeRun::idle))
+        })
+        .collect()
+}
+
+impl FlowRuns {
+    fn get(&self, run_id: &str) -> Result<Arc<FlowRun>, String> {
+        self.runs
+            .lock()
+            .unwrap()
+            .get(run_id)
+            .cloned()
+            .ok_or_else(|| format!("Unknown run '{run_id}'"))
+    }
+
+    fn insert(&self, run: Arc<FlowRun>) {
+        let mut runs = self.runs.lock().unwrap();
+        let mut finished: Vec<(String, String)> = runs
+            .values()
+            .filter_map(|r| {
+                let s = r.status.lock().unwrap();
+                s.finished_at.clone().map(|f| (f, r.id.clone()))
+            })
+            .collect();
+        if finished.len() >= MAX_FINISHED_RUNS {
+            finished.sort();
+            for (_, id) in finished.iter().take(finished.len() + 1 - MAX_FINISHED_RUNS) {
+                runs.remove(id);
+            }
+        }
+        runs.insert(run.id.clone(), run);
+    }
+}
+
+// ── Tauri commands ─────────────────────────────────────────────────────────────
+
+/// Start running `flow` in the backend. Returns the run ID immediately;
+/// progress is streamed over `on_event` and available via `get_run_status`.
+#[tauri::command]
+pub async fn run_flow(
+    app: AppHandle,
+    runs: State<'_, FlowRuns>,
+    flow: FlowDefinition,
+    options: Option<RunOptions>,
+    on_event: Channel<FlowEvent>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let batches = plan_batches(&flow.nodes, &flow.edges)?;
+    let max_parallel = options
+        .max_parallel
+        .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(4)
+        .max(1);
+
+    let seq = runs.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+    let run_id = format!("run-{}-{}", chrono::Utc::now().timestamp_millis(), seq);
+
+    let prior = match &options.resume_from {
+        Some(prior_id) => {
+            let prior = runs
+                .get(prior_id)
+                .map_err(|_| format!("Cannot resume run '{prior_id}': it is no longer available"))?
+                .snapshot();
+            if prior.finished_at.is_none() {
+                return Err(format!("Cannot resume run '{prior_id}' while it is still running"));
+            }
+            Some(prior)
+        }
+        None => None,
+    };
+    let nodes = initial_nodes(&flow, prior.as_ref(), &options.rerun);
+
+    let run = Arc::new(FlowRun::new(&run_id, &flow.id, nodes, on_event, max_parallel, options.step));
+    runs.insert(run.clone());
+
+    tauri::async_runtime::spawn(drive_run(app, run, flow, batches, options));
+    Ok(run_id)
+}
+
+/// Snapshot of a run's overall state and every node's status and result.
+#[tauri::command]
+pub fn get_run_status(runs: State<'_, FlowRuns>, run_id: String) -> Result<RunStatus, String> {
+    Ok(runs.get(&run_id)?.snapshot())
+}
+
+/// Stop scheduling new nodes and kill every node of the run that is still
+/// executing. Returns the run once it has settled, with those nodes cancelled.
+#[tauri::command]
+pub async fn cancel_run(app: AppHandle, runs: State<'_, FlowRuns>, run_id: String) -> Result<RunStatus, String> {
+    let run = runs.get(&run_id)?;
+    run.cancel.send_replace(true);
+
+    let pending = app.state::<ProcessRegistry>().cancel(None, Some(&run_id));
+    for rx in pending {
+        wait_finished(rx).await;
+    }
+    run.finished().await;
+    Ok(run.snapshot())
+}
+
+/// Let the node paused by the debugger start. `step` turns step mode on or
+/// off for the rest of the run; off also releases whatever is paused.
+#[tauri::command]
+pub fn resume_node(
+    runs: State<'_, FlowRuns>,
+    run_id: String,
+    node_id: Option<String>,
+    step: Option<bool>,
+) -> Result<(), String> {
+    let run = runs.get(&run_id)?;
+    let resumed = node_id.as_deref().map(|id| run.resume(id));
+    if let Some(step) = step {
+        run.set_step(step);
+    }
+    match (resumed, node_id) {
+        (Some(false), Some(id)) => Err(format!("Node '{id}' is not paused")),
+        _ => Ok(()),
+    }
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::docker::ContainerRuntime;
+    use crate::commands::termination::Termination;
+
+    fn node(id: &str) -> FlowNode {
+        FlowNode {
+            id: id.into(),
+            node_type: "scriptRun".into(),
+            label: id.into(),
+            config: serde_json::json!({ "command": "true" }),
+            position: Position::default(),
+        }
+    }
+
+    fn edge(source: &str, target: &str) -> FlowEdge {
+        FlowEdge { id: format!("{source}-{target}"), source: source.into(), target: target.into(), label: None }
+    }
+
+    fn flow_run(ids: &[&str], step: bool) -> Arc<FlowRun> {
+        let nodes = ids.iter().map(|id| (id.to_string(), NodeRun::idle())).collect();
+        Arc::new(FlowRun::new("run-1", "flow-1", nodes, Channel::new(|_| Ok(())), 4, step))
+    }
+
+    async fn until_paused(run: &FlowRun, node_id: &str) {
+        for _ in 0..100 {
+            if run.node_status(node_id) == Some(NodeStatus::Paused) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("{node_id} never paused");
+    }
+
+    #[tokio::test]
+    async fn test_step_mode_pauses_one_node_at_a_time() {
+        let run = flow_run(&["a", "b"], true);
+        let first = tokio::spawn({
+            let run = run.clone();
+            async move { run.pause_before("a").await }
+        });
+        until_paused(&run, "a").await;
+        let second = tokio::spawn({
+            let run = run.clone();
+            async move { run.pause_before("b").await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(run.node_status("b"), Some(NodeStatus::Idle));
+        assert!(!run.resume("b"));
+
+        assert!(run.resume("a"));
+        assert!(first.await.unwrap());
+        until_paused(&run, "b").await;
+        // Leaving step mode lets the paused node go
+        run.set_step(false);
+        assert!(second.await.unwrap());
+        assert!(run.pause_before("c").await);
+
+        let run = flow_run(&["a"], true);
+        let paused = tokio::spawn({
+            let run = run.clone();
+            async move { run.pause_before("a").await }
+        });
+        until_paused(&run, "a").await;
+        run.cancel.send_replace(true);
+        assert!(!paused.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_finished_waits_for_the_final_state() {
+        let run = flow_run(&["a", "b"], false);
+        run.set_node("a", NodeStatus::Success, None, None);
+        run.cancel.send_replace(true);
+        let waiter = tokio::spawn({
+            let run = run.clone();
+            async move {
+                run.finished().await;
+                run.snapshot()
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+
+        assert_eq!(run.finish(), RunState::Cancelled);
+        run.done.send_replace(true);
+        let status = waiter.await.unwrap();
+        assert!(status.finished_at.is_some());
+        assert_eq!(status.nodes["a"].status, NodeStatus::Success);
+        assert_eq!(status.nodes["b"].status, NodeStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_resume_carries_outputs_over() {
+        let prior = flow_run(&["build", "test", "deploy"], false);
+        let mut built = CommandResult::cancelled_before_start(5);
+        built.cancelled = false;
+        built.exit_code = 0;
+        built.outputs.insert("digest".into(), "sha256:abc".into());
+        prior.set_node("build", NodeStatus::Success, Some(built), None);
+        prior.set_node("test", NodeStatus::Error, None, Some("exit code 1".into()));
+        prior.finish();
+        let prior = prior.snapshot();
+
+        let flow = FlowDefinition {
+            id: "flow-1".into(),
+            name: "Flow".into(),
+            description: None,
+            nodes: vec![node("build"), node("test"), node("deploy")],
+            edges: vec![edge("build", "test"), edge("test", "deploy")],
+            created_at: None,
+            updated_at: None,
+        };
+        let nodes = initial_nodes(&flow, Some(&prior), &[]);
+        assert_eq!(nodes["build"].status, NodeStatus::Success);
+        assert_eq!(nodes["test"].status, NodeStatus::Idle);
+        assert_eq!(nodes["deploy"].status, NodeStatus::Idle);
+
+        let run = FlowRun::new("run-2", "flow-1", nodes, Channel::new(|_| Ok(())), 4, false);
+        let config = serde_json::json!({ "command": "deploy ${{ nodes.build.outputs.digest }}" });
+        let config = render(&config, &run.outputs()).unwrap();
+        assert_eq!(config["command"], "deploy sha256:abc");
+
+        let nodes = initial_nodes(&flow, Some(&prior), &["build".to_string()]);
+        assert_eq!(nodes["build"].status, NodeStatus::Idle);
+    }
+
+    #[test]
+    fn test_resume_keeps_false_conditions_closed() {
+        let prior = flow_run(&["build", "check", "deploy"], false);
+        prior.set_node("build", NodeStatus::Success, None, None);
+        prior.set_condition("check", false);
+        prior.set_node("check", NodeStatus::Success, None, None);
+        prior.set_node("deploy", NodeStatus::Skipped, None, None);
+        prior.finish();
+        let prior = prior.snapshot();
+        assert_eq!(prior.nodes["check"].condition_met, Some(false));
+
+        let flow = FlowDefinition {
+            id: "flow-1".into(),
+            name: "Flow".into(),
+            description: None,
+            nodes: vec![node("build"), node("check"), node("deploy")],
+            edges: vec![edge("build", "check"), edge("check", "deploy")],
+            created_at: None,
+            updated_at: None,
+        };
+        let nodes = initial_nodes(&flow, Some(&prior), &[]);
+        let run = FlowRun::new("run-2", "flow-1", nodes, Channel::new(|_| Ok(())), 4, false);
+        let passed = run.passed();
+        assert_eq!(passed, HashSet::from(["build".to_string()]));
+        let status = |id: &str| run.node_status(id);
+        assert!(!should_run("deploy", RunWhen::OnSuccess, &flow.edges, &passed, status));
+    }
+
+    #[test]
+    fn test_matrix_result_aggregates_instances() {
+        let instance = |exit_code: i32, memory: u64, outputs: &[(&str, &str)]| {
+            let mut r = CommandResult::cancelled_before_start(100);
+            r.cancelled = false;
+            r.exit_code = exit_code;
+            r.termination = Termination::Exited { code: exit_code };
+            r.max_memory_mb = memory;
+            r.disk_written_bytes = 10;
+            r.stdout = "instance output".into();
+            r.outputs = outputs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            r
+        };
+        let instances = vec![
+            (NodeStatus::Success, Some(instance(0, 300, &[("digest", "sha256:a"), ("registry", "ghcr.io")]))),
+            (NodeStatus::Error, Some(instance(2, 100, &[]))),
+            (NodeStatus::Success, Some(instance(0, 200, &[("digest", "sha256:b"), ("registry", "ghcr.io")]))),
+            (NodeStatus::Skipped, None),
+        ];
+        let (result, conflicts) = aggregate_matrix(&instances, 1500).unwrap();
+        assert_eq!(result.exit_code, 2);
+        assert_eq!(result.termination, Termination::Exited { code: 2 });
+        assert_eq!(result.duration_ms, 1500);
+        assert_eq!(result.max_memory_mb, 300);
+        assert_eq!(result.disk_written_bytes, 30);
+        assert!(result.stdout.is_empty());
+        assert_eq!(result.outputs["digest"], "sha256:b");
+        assert_eq!(result.outputs["registry"], "ghcr.io");
+        assert_eq!(conflicts, vec!["digest".to_string()]);
+
+        let passed = vec![instances[0].clone(), instances[2].clone()];
+        assert_eq!(aggregate_matrix(&passed, 10).unwrap().0.exit_code, 0);
+        assert!(aggregate_matrix(&[(NodeStatus::Cancelled, None)], 10).is_none());
+    }
+
+    #[test]
+    fn test_plan_batches_layers_diamond() {
+        let nodes = vec![node("a"), node("b"), node("c"), node("d")];
+        let edges = vec![edge("a", "b"), edge("a", "c"), edge("b", "d"), edge("c", "d")];
+        let batches = plan_batches(&nodes, &edges).unwrap();
+        assert_eq!(batches, vec![vec!["a"], vec!["b", "c"], vec!["d"]]);
+    }
+
+    #[test]
+    fn test_plan_batches_rejects_cycle() {
+        let nodes = vec![node("a"), node("b")];
+        let edges = vec![edge("a", "b"), edge("b", "a")];
+        assert!(plan_batches(&nodes, &edges).is_err());
+    }
+
+    #[test]
+    fn test_plan_batches_ignores_dangling_edges() {
+        let nodes = vec![node("a")];
+        let edges = vec![edge("a", "ghost")];
+        assert_eq!(plan_batches(&nodes, &edges).unwrap(), vec![vec!["a"]]);
+    }
+
+    #[test]
+    fn test_ancestors_are_transitive() {
+        let edges = vec![edge("a", "b"), edge("b", "c"), edge("x", "y")];
+        let mut ancestors = ancestors_of("c", &edges);
+        ancestors.sort();
+        assert_eq!(ancestors, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_run_when_after_failure() {
+        // build fails → test is skipped → deploy (on_success) is skipped,
+        // rollback (on_failure) and teardown (always) still run
+        let edges = vec![edge("build", "test"), edge("test", "deploy"), edge("test", "rollback"), edge("test", "teardown")];
+        let status = |id: &str| match id {
+            "build" => Some(NodeStatus::Error),
+            "test" => Some(NodeStatus::Skipped),
+            _ => Some(NodeStatus::Idle),
+        };
+        let passed = HashSet::new();
+        assert!(!should_run("test", RunWhen::OnSuccess, &edges, &passed, status));
+        assert!(!should_run("deploy", RunWhen::OnSuccess, &edges, &passed, status));
+        assert!(should_run("rollback", RunWhen::OnFailure, &edges, &passed, status));
+        assert!(should_run("teardown", RunWhen::Always, &edges, &passed, status));
+
+        let passed = HashSet::from(["build".to_string(), "test".to_string()]);
+        let all_ok = |_: &str| Some(NodeStatus::Success);
+        assert!(should_run("deploy", RunWhen::OnSuccess, &edges, &passed, all_ok));
+        assert!(!should_run("rollback", RunWhen::OnFailure, &edges, &passed, all_ok));
+    }
+
+    #[test]
+    fn test_failure_policy_from_config() {
+        let policy = FailurePolicy::from_config(&serde_json::json!({
+            "allowedExitCodes": [1], "continueOnError": true, "runWhen": "always"
+        }))
+        .unwrap();
+        assert_eq!(policy, FailurePolicy { allowed_exit_codes: vec![1], continue_on_error: true, run_when: RunWhen::Always });
+
+        let typed = FailurePolicy::from_config(&serde_json::json!({ "allowedExitCodes": "1, 3", "runWhen": "" })).unwrap();
+        assert_eq!(typed.allowed_exit_codes, vec![1, 3]);
+        assert_eq!(typed.run_when, RunWhen::OnSuccess);
+
+        assert!(FailurePolicy::from_config(&serde_json::json!({ "allowedExitCodes": "one" })).is_err());
+        assert!(FailurePolicy::from_config(&serde_json::json!({ "runWhen": "sometimes" })).is_err());
+        assert_eq!(FailurePolicy::from_config(&serde_json::json!({})).unwrap(), FailurePolicy::default());
+    }
+
+    #[test]
+    fn test_node_status_serializes_snake_case() {
+        assert_eq!(serde_json::to_value(NodeStatus::SuccessWithWarnings).unwrap(), "success_with_warnings");
+        assert_eq!(serde_json::to_value(NodeStatus::Skipped).unwrap(), "skipped");
+    }
+
+    #[test]
+    fn test_node_program_script_vs_argv() {
+        let script = node("a");
+        assert_eq!(node_program(&script, None).unwrap(), Some(Program::Shell("true".into())));
+
+        let mut build = node("b");
+        build.node_type = "dockerBuild".into();
+        build.config = serde_json::json!({ "tag": "app:1", "context": "." });
+        assert_eq!(
+            node_program(&build, None).unwrap(),
+            Some(Program::Argv(vec!["docker".into(), "build".into(), "-t".into(), "app:1".into(), "--".into(), ".".into()]))
+        );
+
+        let mut delay = node("c");
+        delay.node_type = "delayNode".into();
+        assert_eq!(node_program(&delay, None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_env_values_become_secret_references() {
+        assert_eq!(env_value(&serde_json::json!("plain")), Some(EnvValue::Plain("plain".into())));
+        assert_eq!(
+            env_value(&serde_json::json!("$SECRET_NPM_TOKEN")),
+            Some(EnvValue::Secret { secret: "NPM_TOKEN".into() })
+        );
+        assert_eq!(
+            env_value(&serde_json::json!({ "secret": "NPM_TOKEN" })),
+            Some(EnvValue::Secret { secret: "NPM_TOKEN".into() })
+        );
+        assert_eq!(env_value(&serde_json::json!(42)), None);
+    }
+
+    #[test]
+    fn test_docker_settings_from_panel_fields() {
+        let settings: ExecutionSettings = serde_json::from_value(serde_json::json!({
+            "profile": "docker", "dockerImage": "", "dockerVolumes": "/a:/a:ro\n\n/b:/b",
+            "dockerPull": "missing", "dockerNetwork": "", "dockerUser": "host", "dockerRuntime": "podman"
+        }))
+        .unwrap();
+        let cfg = settings.docker_config().unwrap();
+        assert_eq!(cfg.image, None);
+        assert_eq!(cfg.volumes.len(), 2);
+        assert_eq!(cfg.network, None);
+        assert_eq!(cfg.user.as_deref(), Some("host"));
+        assert_eq!(cfg.runtime, Some(ContainerRuntime::Podman));
+
+        let bad: ExecutionSettings = serde_json::from_value(serde_json::json!({ "dockerPull": "sometimes" })).unwrap();
+        assert!(bad.docker_config().is_err());
+    }
+
+    #[test]
+    fn test_invalid_execution_profile_fails_the_node() {
+        let mut ssh = node("deploy");
+        ssh.config = serde_json::json!({ "executionProfile": { "profile": "ssh", "sshHost": "h", "sshPort": 70000 } });
+        let err = exec_request(&ssh, "run-1", Program::Shell("true".into()), None).unwrap_err();
+        assert!(err.contains("Invalid execution profile for node deploy"), "{err}");
+
+        let plain = exec_request(&node("build"), "run-1", Program::Shell("true".into()), None).unwrap();
+        assert!(matches!(plain.profile, ExecutionProfile::Native));
+    }
+
+    #[test]
+    fn test_flow_definition_deserializes_ui_shape() {
+        let json = serde_json::json!({
+            "id": "f1", "name": "Deploy",
+            "nodes": [{ "id": "n1", "type": "scriptRun", "label": "Echo",
+                        "config": { "command": "echo hi" }, "status": "idle",
+                        "position": { "x": 10, "y": 20 } }],
+            "edges": [],
+            "createdAt": "2024-01-01T00:00:00Z", "updatedAt": "2024-01-01T00:00:00Z"
+        });
+        let flow: FlowDefinition = serde_json::from_value(json).unwrap();
+        assert_eq!(flow.nodes[0].node_type, "scriptRun");
+        assert_eq!(flow.nodes[0].position.x, 10.0);
+    }
+}
@@ -0,0 +1,413 @@
+// ============================================================
+// DevFlow Studio — Native Resource Limits
+// Confines a host-run node to a transient cgroup v2 leaf with
+// cpu.max / memory.max / pids.max / io.weight, so a runaway
+// build can't take the machine down. The app moves itself into
+// a leaf of its own (delegated) cgroup once, so that node
+// cgroups can be created beside it. Where cgroups aren't
+// delegated, memory falls back to RLIMIT_AS.
+// ============================================================
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::process::Command;
+
+/// cgroup v2 `cpu.max` period, in microseconds.
+const CPU_PERIOD_US: u64 = 100_000;
+
+/// Limits for a node running on the host (native and sandbox profiles).
+#[derive(Debug, Deserialize, Default, Clone, PartialEq)]
+pub struct ResourceLimits {
+    /// CPU quota in cores, e.g. `1.5`.
+    pub cpu: Option<f64>,
+    /// Memory ceiling, e.g. `512m` or `2g`.
+    pub memory: Option<String>,
+    /// Maximum number of processes and threads.
+    pub pids: Option<u64>,
+    /// Relative IO weight, 1–10000 (default 100).
+    pub io_weight: Option<u16>,
+}
+
+impl ResourceLimits {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn memory_bytes(&self) -> Result<Option<u64>, String> {
+        self.memory.as_deref().map(parse_size).transpose()
+    }
+}
+
+/// Parse a size like `512m`, `2G`, `1.5g` or `1048576` (binary units).
+pub fn parse_size(text: &str) -> Result<u64, String> {
+    let lower = text.trim().to_ascii_lowercase();
+    let digits = lower.trim_end_matches("ib").trim_end_matches('b');
+    let (number, unit) = match digits.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&digits[..i], c),
+        _ => (digits, ' '),
+    };
+    let multiplier: u64 = match unit {
+        ' ' => 1,
+        'k' => 1 << 10,
+        'm' => 1 << 20,
+        'g' => 1 << 30,
+        't' => 1 << 40,
+        _ => return Err(format!("Invalid size '{text}': unknown unit")),
+    };
+    let value: f64 = number.trim().parse().map_err(|_| format!("Invalid size '{text}'"))?;
+    if !value.is_finite() || value <= 0.0 {
+        return Err(format!("Invalid size '{text}': must be positive"));
+    }
+    Ok((value * multiplier as f64) as u64)
+}
+
+/// How a node's limits are enforced.
+pub enum Confinement {
+    /// No limits requested.
+    None,
+    Cgroup(NodeCgroup),
+    /// Per-process rlimits set just before exec.
+    Rlimit { memory_bytes: Option<u64> },
+}
+
+impl Confinement {
+    /// Set up enforcement for `limits`. The second value is a warning to show
+    /// when some limits can't be enforced on this host.
+    pub fn new(limits: &ResourceLimits, node_id: &str) -> Result<(Self, Option<String>), String> {
+        if limits.is_empty() {
+            return Ok((Confinement::None, None));
+        }
+        let memory_bytes = limits.memory_bytes()?;
+        if let Some(cores) = limits.cpu {
+            if !cores.is_finite() || cores <= 0.0 {
+                return Err(format!("Invalid CPU limit {cores}: must be a positive number of cores"));
+            }
+        }
+        if let Some(weight) = limits.io_weight {
+            if !(1..=10_000).contains(&weight) {
+                return Err(format!("Invalid IO weight {weight}: must be 1–10000"));
+            }
+        }
+
+        match NodeCgroup::create(limits, memory_bytes, node_id) {
+            Ok(cgroup) => Ok((Confinement::Cgroup(cgroup), None)),
+            Err(reason) => {
+                let mut unenforced = Vec::new();
+                if limits.cpu.is_some() {
+                    unenforced.push("CPU");
+                }
+                if limits.pids.is_some() {
+                    unenforced.push("pids");
+                }
+                if limits.io_weight.is_some() {
+                    unenforced.push("IO");
+                }
+                let mut warning = format!("cgroup v2 limits unavailable ({reason})");
+                if memory_bytes.is_some() {
+                    warning.push_str("; memory limited via RLIMIT_AS");
+                }
+                if !unenforced.is_empty() {
+                    warning.push_str(&format!("; {} limits not enforced", unenforced.join("/")));
+                }
+                Ok((Confinement::Rlimit { memory_bytes }, Some(warning)))
+            }
+        }
+    }
+
+    /// Arrange for the child to enter its cgroup (or set its rlimits) between
+    /// fork and exec, so not even its first instruction runs unconfined.
+    pub fn apply(&self, cmd: &mut Command) {
+        match self {
+            Confinement::None => {}
+            Confinement::Cgroup(cgroup) => cgroup.apply(cmd),
+            Confinement::Rlimit { memory_bytes } => apply_rlimits(cmd, *memory_bytes),
+        }
+    }
+
+    /// Whether the kernel OOM-killed a process in the node's cgroup.
+    pub fn oom_killed(&self) -> bool {
+        match self {
+            Confinement::Cgroup(cgroup) => cgroup.oom_kills() > 0,
+            _ => false,
+        }
+    }
+
+    /// Kill what is left of the node and remove its cgroup, off the async runtime.
+    pub async fn release(self) -> Result<(), String> {
+        match self {
+            Confinement::Cgroup(cgroup) => cgroup.remove().await,
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn apply_rlimits(cmd: &mut Command, memory_bytes: Option<u64>) {
+    let Some(bytes) = memory_bytes else { return };
+    let limit = libc::rlimit { rlim_cur: bytes as libc::rlim_t, rlim_max: bytes as libc::rlim_t };
+    // SAFETY: setrlimit is async-signal-safe and `limit` is a plain copy
+    unsafe {
+        cmd.pre_exec(move || {
+            if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn apply_rlimits(_cmd: &mut Command, _memory_bytes: Option<u64>) {}
+
+// ── cgroup v2 ─────────────────────────────────────────────────────────────────
+
+/// A per-execution cgroup, killed and removed by `Confinement::release`, or on
+/// drop if an error path skipped that.
+pub struct NodeCgroup {
+    path: PathBuf,
+    #[cfg(unix)]
+    procs: std::ffi::CString,
+    removed: bool,
+}
+
+impl NodeCgroup {
+    #[cfg(target_os = "linux")]
+    fn create(limits: &ResourceLimits, memory_bytes: Option<u64>, node_id: &str) -> Result<Self, String> {
+        use std::os::unix::ffi::OsStrExt;
+        use std::sync::atomic::{AtomicU64, Ordering};
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+        let parent = delegated_parent()?;
+        let name = format!(
+            "devflow-{}-{}",
+            super::output::sanitize_file_component(node_id),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = parent.join(name);
+        std::fs::create_dir(&path).map_err(|e| format!("cannot create {}: {e}", path.display()))?;
+        let procs = std::ffi::CString::new(path.join("cgroup.procs").as_os_str().as_bytes())
+            .map_err(|_| "cgroup path contains a NUL byte".to_string())?;
+        // Owned from here on, so a failed limit write still removes the directory
+        let cgroup = Self { path, procs, removed: false };
+
+        if let Some(cores) = limits.cpu {
+            let quota = ((cores * CPU_PERIOD_US as f64) as u64).max(1_000);
+            cgroup.write("cpu.max", &format!("{quota} {CPU_PERIOD_US}"))?;
+        }
+        if let Some(bytes) = memory_bytes {
+            cgroup.write("memory.max", &bytes.to_string())?;
+            // Otherwise the limit only pushes the node into swap
+            let _ = cgroup.write("memory.swap.max", "0");
+        }
+        if let Some(pids) = limits.pids {
+            cgroup.write("pids.max", &pids.to_string())?;
+        }
+        if let Some(weight) = limits.io_weight {
+            cgroup.write("io.weight", &format!("default {weight}"))?;
+        }
+        Ok(cgroup)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn create(_: &ResourceLimits, _: Option<u64>, _: &str) -> Result<Self, String> {
+        Err("cgroups are Linux-only".into())
+    }
+
+    fn write(&self, file: &str, value: &str) -> Result<(), String> {
+        std::fs::write(self.path.join(file), value).map_err(|e| format!("cannot set {file}: {e}"))
+    }
+
+    #[cfg(unix)]
+    fn apply(&self, cmd: &mut Command) {
+        let procs = self.procs.clone();
+        // SAFETY: only open/write/close run in the child, all async-signal-safe.
+        // Writing "0" to cgroup.procs moves the writing process itself.
+        unsafe {
+            cmd.pre_exec(move || {
+                let fd = libc::open(procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+                if fd < 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                let written = libc::write(fd, b"0".as_ptr().cast(), 1);
+                let err = std::io::Error::last_os_error();
+                libc::close(fd);
+                if written != 1 {
+                    return Err(err);
+                }
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn apply(&self, _cmd: &mut Command) {}
+
+    fn oom_kills(&self) -> u64 {
+        let events = std::fs::read_to_string(self.path.join("memory.events")).unwrap_or_default();
+        parse_oom_kills(&events)
+    }
+}
+
+impl NodeCgroup {
+    async fn remove(mut self) -> Result<(), String> {
+        self.removed = true;
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || remove_cgroup(&path))
+            .await
+            .map_err(|e| format!("cgroup cleanup failed: {e}"))?
+    }
+}
+
+impl Drop for NodeCgroup {
+    fn drop(&mut self) {
+        if self.removed {
+            return;
+        }
+        let path = self.path.clone();
+        let cleanup = move || {
+            if let Err(e) = remove_cgroup(&path) {
+                eprintln!("devflow: {e}");
+            }
+        };
+        // Removal polls until the killed processes are reaped; keep that off the runtime
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => drop(runtime.spawn_blocking(cleanup)),
+            Err(_) => cleanup(),
+        }
+    }
+}
+
+/// Kill whatever is left in a node cgroup and remove it. Blocks until the
+/// kernel has reaped the processes, for up to a second.
+fn remove_cgroup(path: &Path) -> Result<(), String> {
+    // Anything that escaped the process group is still in here
+    let kill_file = std::fs::write(path.join("cgroup.kill"), "1").is_ok();
+    let mut last_error = None;
+    for _ in 0..50 {
+        if !kill_file {
+            // cgroup.kill is Linux 5.14+; before that, kill the members one by one
+            kill_members(path);
+        }
+        match std::fs::remove_dir(path) {
+            Ok(()) => return Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => last_error = Some(e),
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    let reason = last_error.map(|e| e.to_string()).unwrap_or_default();
+    Err(format!("cannot remove cgroup {}: {reason}", path.display()))
+}
+
+#[cfg(unix)]
+fn kill_members(path: &Path) {
+    let procs = std::fs::read_to_string(path.join("cgroup.procs")).unwrap_or_default();
+    for pid in procs.lines().filter_map(|line| line.trim().parse::<libc::pid_t>().ok()) {
+        // SAFETY: kill(2) has no memory-safety preconditions
+        unsafe {
+            libc::kill(pid, libc::SIGKILL);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_members(_path: &Path) {}
+
+fn parse_oom_kills(memory_events: &str) -> u64 {
+    memory_events
+        .lines()
+        .find_map(|line| line.strip_prefix("oom_kill "))
+        .and_then(|n| n.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// The cgroup node cgroups are created in: the app's own cgroup, after
+/// moving the app into a `devflow-app` leaf of it (cgroup v2 forbids
+/// enabling controllers for children while the parent holds processes).
+/// Done once; the outcome is cached for the life of the app.
+#[cfg(target_os = "linux")]
+fn delegated_parent() -> Result<&'static std::path::Path, String> {
+    use std::path::Path;
+    use std::sync::OnceLock;
+    static PARENT: OnceLock<Result<PathBuf, String>> = OnceLock::new();
+
+    PARENT
+        .get_or_init(|| {
+            let own = std::fs::read_to_string("/proc/self/cgroup").map_err(|e| format!("no cgroup info: {e}"))?;
+            let relative = own
+                .lines()
+                .find_map(|line| line.strip_prefix("0::"))
+                .ok_or("cgroup v2 is not mounted")?;
+            let parent = Path::new("/sys/fs/cgroup").join(relative.trim().trim_start_matches('/'));
+
+            let leaf = parent.join("devflow-app");
+            if !parent.ends_with("devflow-app") {
+                std::fs::create_dir_all(&leaf).map_err(|e| format!("cgroup not delegated: {e}"))?;
+                std::fs::write(leaf.join("cgroup.procs"), std::process::id().to_string())
+                    .map_err(|e| format!("cannot move into {}: {e}", leaf.display()))?;
+            }
+            let parent = if parent.ends_with("devflow-app") { parent.parent().unwrap().to_path_buf() } else { parent };
+
+            let available = std::fs::read_to_string(parent.join("cgroup.controllers")).unwrap_or_default();
+            for controller in ["cpu", "memory", "pids", "io"] {
+                if available.split_whitespace().any(|c| c == controller) {
+                    let _ = std::fs::write(parent.join("cgroup.subtree_control"), format!("+{controller}"));
+                }
+            }
+            Ok(parent)
+        })
+        .as_ref()
+        .map(PathBuf::as_path)
+        .map_err(Clone::clone)
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512m").unwrap(), 512 << 20);
+        assert_eq!(parse_size("2G").unwrap(), 2 << 30);
+        assert_eq!(parse_size("1.5gb").unwrap(), 3 << 29);
+        assert_eq!(parse_size("4KiB").unwrap(), 4096);
+        assert_eq!(parse_size("1048576").unwrap(), 1 << 20);
+        assert!(parse_size("12x").is_err());
+        assert!(parse_size("-1m").is_err());
+        assert!(parse_size("").is_err());
+    }
+
+    #[test]
+    fn test_parse_oom_kills() {
+        let events = "low 0\nhigh 0\nmax 12\noom 3\noom_kill 2\noom_group_kill 0\n";
+        assert_eq!(parse_oom_kills(events), 2);
+        assert_eq!(parse_oom_kills(""), 0);
+    }
+
+    #[test]
+    fn test_confinement_validates_limits() {
+        assert!(matches!(Confinement::new(&ResourceLimits::default(), "n").unwrap().0, Confinement::None));
+        let bad_cpu = ResourceLimits { cpu: Some(0.0), ..Default::default() };
+        assert!(Confinement::new(&bad_cpu, "n").is_err());
+        let bad_io = ResourceLimits { io_weight: Some(0), ..Default::default() };
+        assert!(Confinement::new(&bad_io, "n").is_err());
+        let bad_mem = ResourceLimits { memory: Some("lots".into()), ..Default::default() };
+        assert!(Confinement::new(&bad_mem, "n").is_err());
+    }
+
+    #[test]
+    fn test_remove_cgroup_reports_a_leak() {
+        let dir = std::env::temp_dir().join(format!("devflow-cgroup-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(remove_cgroup(&dir.join("gone")).is_ok());
+
+        // Outside cgroupfs, cgroup.kill is an ordinary file that keeps the directory busy
+        let err = remove_cgroup(&dir).unwrap_err();
+        assert!(err.contains("cannot remove cgroup"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,171 @@
+// ============================================================
+// DevFlow Studio — Structured Log Events
+// Every line a node produces is sent to the webview as a
+// `LogEvent` with a process-wide monotonic sequence number,
+// an RFC 3339 timestamp and the run/node it belongs to.
+// ============================================================
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use tauri::ipc::Channel;
+
+/// Sequence counter shared by every execution, so events from concurrent
+/// nodes and flows can be totally ordered on the UI side.
+static NEXT_SEQ: AtomicU64 = AtomicU64::new(1);
+
+/// Which output stream a log event came from.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+    /// Messages generated by DevFlow itself (timeouts, cancellation, …).
+    System,
+}
+
+/// Severity of a log event.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+/// Whether the event is a finished line or a `\r` progress update.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogKind {
+    Line,
+    Progress,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEvent {
+    pub seq: u64,
+    pub timestamp: String,
+    pub run_id: Option<String>,
+    pub node_id: String,
+    pub stream: LogStream,
+    pub level: LogLevel,
+    pub kind: LogKind,
+    pub text: String,
+}
+
+impl LogEvent {
+    pub fn new(
+        run_id: Option<String>,
+        node_id: String,
+        stream: LogStream,
+        level: LogLevel,
+        kind: LogKind,
+        text: String,
+    ) -> Self {
+        Self {
+            seq: NEXT_SEQ.fetch_add(1, Ordering::Relaxed),
+            timestamp: now_rfc3339(),
+            run_id,
+            node_id,
+            stream,
+            level,
+            kind,
+            text,
+        }
+    }
+}
+
+/// Current time as an RFC 3339 string with millisecond precision.
+pub fn now_rfc3339() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+type LogSink = Arc<dyn Fn(LogEvent) + Send + Sync>;
+
+/// Per-invocation sender that stamps events with the node and run they belong to.
+#[derive(Clone)]
+pub struct LogEmitter {
+    sink: LogSink,
+    run_id: Option<String>,
+    node_id: String,
+}
+
+impl LogEmitter {
+    /// Send events over the `Channel` passed to `execute_command`.
+    pub fn new(channel: Channel<LogEvent>, node_id: &str, run_id: Option<&str>) -> Self {
+        Self::with_sink(move |event| { let _ = channel.send(event); }, node_id, run_id)
+    }
+
+    /// Send events to an arbitrary sink (e.g. the flow engine's run channel).
+    pub fn with_sink<F>(sink: F, node_id: &str, run_id: Option<&str>) -> Self
+    where
+        F: Fn(LogEvent) + Send + Sync + 'static,
+    {
+        Self {
+            sink: Arc::new(sink),
+            run_id: run_id.map(str::to_string),
+            node_id: node_id.to_string(),
+        }
+    }
+
+    /// A complete line of child output.
+    pub fn line(&self, stream: LogStream, text: String) {
+        let level = if stream == LogStream::Stderr { LogLevel::Warn } else { LogLevel::Info };
+        self.send(stream, level, LogKind::Line, text);
+    }
+
+    /// An intermediate `\r` progress update.
+    pub fn progress(&self, stream: LogStream, text: String) {
+        self.send(stream, LogLevel::Info, LogKind::Progress, text);
+    }
+
+    /// A message generated by the executor itself.
+    pub fn system(&self, level: LogLevel, text: String) {
+        self.send(LogStream::System, level, LogKind::Line, text);
+    }
+
+    fn send(&self, stream: LogStream, level: LogLevel, kind: LogKind, text: String) {
+        let event = LogEvent::new(self.run_id.clone(), self.node_id.clone(), stream, level, kind, text);
+        (self.sink)(event);
+    }
+}
+
+impl LogStream {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogStream::Stdout => "stdout",
+            LogStream::Stderr => "stderr",
+            LogStream::System => "system",
+        }
+    }
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequence_is_monotonic() {
+        let a = LogEvent::new(None, "n".into(), LogStream::Stdout, LogLevel::Info, LogKind::Line, "a".into());
+        let b = LogEvent::new(None, "n".into(), LogStream::Stdout, LogLevel::Info, LogKind::Line, "b".into());
+        assert!(b.seq > a.seq);
+    }
+
+    #[test]
+    fn test_serializes_camel_case_with_rfc3339_timestamp() {
+        let ev = LogEvent::new(
+            Some("run-1".into()), "build".into(), LogStream::Stderr, LogLevel::Warn, LogKind::Progress, "50%".into(),
+        );
+        let json = serde_json::to_value(&ev).unwrap();
+        assert_eq!(json["runId"], "run-1");
+        assert_eq!(json["nodeId"], "build");
+        assert_eq!(json["stream"], "stderr");
+        assert_eq!(json["kind"], "progress");
+        assert!(chrono::DateTime::parse_from_rfc3339(json["timestamp"].as_str().unwrap()).is_ok());
+    }
+}
@@ -0,0 +1,285 @@
+// ============================================================
+// DevFlow Studio — Node Matrix Expansion
+// A node's `matrix` (key → list of values, plus include and
+// exclude entries) expands into one instance per combination.
+// Each instance gets the values as MATRIX_<KEY> env vars and in
+// `${{ matrix.<key> }}` references in its config. The flow
+// engine runs instances in parallel and reports them on the
+// parent node.
+// ============================================================
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use super::template::render_matrix;
+
+/// Upper bound on instances, so a typo can't spawn thousands of processes.
+pub const MAX_COMBINATIONS: usize = 256;
+
+/// One instance's matrix values, by key.
+pub type Combination = BTreeMap<String, String>;
+
+/// A node's `config.matrix`. Every key other than the ones below is an axis.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MatrixSpec {
+    /// Extra combinations, or extra values for combinations they match.
+    #[serde(default)]
+    pub include: Vec<BTreeMap<String, Value>>,
+    /// Combinations to drop; an entry matches when all its keys do.
+    #[serde(default)]
+    pub exclude: Vec<BTreeMap<String, Value>>,
+    /// Cancel the remaining instances once one fails (default: true).
+    #[serde(default = "default_fail_fast")]
+    pub fail_fast: bool,
+    /// Instances running at once (default: all).
+    #[serde(default)]
+    pub max_parallel: Option<usize>,
+    #[serde(flatten)]
+    pub axes: BTreeMap<String, Vec<Value>>,
+}
+
+fn default_fail_fast() -> bool {
+    true
+}
+
+/// Matrix values are used as env vars and in strings, so only scalars are allowed.
+fn scalar(key: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        other => Err(format!("Matrix value for '{key}' must be a string, number or boolean, got {other}")),
+    }
+}
+
+fn entry(values: &BTreeMap<String, Value>) -> Result<Combination, String> {
+    values.iter().map(|(k, v)| scalar(k, v).map(|v| (k.clone(), v))).collect()
+}
+
+impl MatrixSpec {
+    /// The node's matrix, if it has one. The settings panel stores it as JSON text.
+    pub fn from_config(cfg: &Value) -> Result<Option<Self>, String> {
+        let value = match cfg.get("matrix") {
+            None | Some(Value::Null) => return Ok(None),
+            Some(Value::String(text)) if text.trim().is_empty() => return Ok(None),
+            Some(Value::String(text)) => {
+                serde_json::from_str(text).map_err(|e| format!("Invalid matrix: {e}"))?
+            }
+            Some(value) => value.clone(),
+        };
+        if value.as_object().is_some_and(Map::is_empty) {
+            return Ok(None);
+        }
+        Self::deserialize(value).map(Some).map_err(|e| format!("Invalid matrix: {e}"))
+    }
+
+    /// Every combination: the product of the axes minus `exclude`, then `include`
+    /// (GitHub Actions semantics).
+    pub fn expand(&self) -> Result<Vec<Combination>, String> {
+        let mut combinations = vec![Combination::new()];
+        for (key, values) in &self.axes {
+            if values.is_empty() {
+                return Err(format!("Matrix key '{key}' has no values"));
+            }
+            let values = values.iter().map(|v| scalar(key, v)).collect::<Result<Vec<_>, _>>()?;
+            combinations = combinations
+                .into_iter()
+                .flat_map(|c| values.iter().map(move |v| {
+                    let mut c = c.clone();
+                    c.insert(key.clone(), v.clone());
+                    c
+                }))
+                .collect();
+            if combinations.len() > MAX_COMBINATIONS {
+                return Err(format!("Matrix expands to more than {MAX_COMBINATIONS} combinations"));
+            }
+        }
+        if self.axes.is_empty() {
+            combinations.clear();
+        }
+
+        let excludes = self.exclude.iter().map(entry).collect::<Result<Vec<_>, _>>()?;
+        combinations.retain(|c| !excludes.iter().any(|ex| ex.iter().all(|(k, v)| c.get(k) == Some(v))));
+        let base = combinations.len();
+
+        for include in self.include.iter().map(entry) {
+            let include = include?;
+            // Values for axis keys must match; other keys are added, never overriding an axis value
+            let mut matched = false;
+            for c in combinations.iter_mut().take(base) {
+                let fits = include.iter().all(|(k, v)| !self.axes.contains_key(k) || c.get(k) == Some(v));
+                if fits {
+                    for (k, v) in &include {
+                        if !self.axes.contains_key(k) {
+                            c.insert(k.clone(), v.clone());
+                        }
+                    }
+                    matched = true;
+                }
+            }
+            if !matched {
+                combinations.push(include);
+            }
+        }
+
+        if combinations.is_empty() {
+            return Err("Matrix has no combinations left after exclude".into());
+        }
+        if combinations.len() > MAX_COMBINATIONS {
+            return Err(format!("Matrix expands to more than {MAX_COMBINATIONS} combinations"));
+        }
+        Ok(combinations)
+    }
+}
+
+/// `node=20, os=linux`, for labels and logs.
+pub fn describe(combination: &Combination) -> String {
+    combination.iter().map(|(k, v)| format!("{k}={v}")).collect::<Vec<_>>().join(", ")
+}
+
+/// `MATRIX_<KEY>`, with anything but ASCII letters and digits as `_`.
+pub fn env_name(key: &str) -> String {
+    let key: String = key.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect();
+    format!("MATRIX_{key}")
+}
+
+/// ID an instance's processes are registered under, so fail-fast can cancel it alone.
+pub fn instance_id(node_id: &str, index: usize) -> String {
+    format!("{node_id}[{index}]")
+}
+
+/// The config one instance runs with: `matrix` removed, `${{ matrix.<key> }}`
+/// substituted and the values added to `envVars`.
+pub fn instance_config(config: &Value, combination: &Combination) -> Result<Value, String> {
+    let mut config = config.clone();
+    if let Some(map) = config.as_object_mut() {
+        map.remove("matrix");
+    }
+    let mut config = render_matrix(&config, combination)?;
+    if let Some(map) = config.as_object_mut() {
+        let env = map.entry("envVars").or_insert_with(|| Value::Object(Map::new()));
+        if !env.is_object() {
+            *env = Value::Object(Map::new());
+        }
+        let env = env.as_object_mut().unwrap();
+        for (k, v) in combination {
+            env.insert(env_name(k), Value::String(v.clone()));
+        }
+    }
+    Ok(config)
+}
+
+#[derive(Debug, Clone)]
+pub struct MatrixInstance {
+    pub index: usize,
+    pub id: String,
+    pub combination: Combination,
+    pub config: Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct MatrixPlan {
+    pub fail_fast: bool,
+    pub max_parallel: Option<usize>,
+    pub instances: Vec<MatrixInstance>,
+}
+
+impl MatrixPlan {
+    pub fn new(node_id: &str, config: &Value, spec: &MatrixSpec) -> Result<Self, String> {
+        let instances = spec
+            .expand()?
+            .into_iter()
+            .enumerate()
+            .map(|(index, combination)| {
+                Ok(MatrixInstance {
+                    index,
+                    id: instance_id(node_id, index),
+                    config: instance_config(config, &combination)?,
+                    combination,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self { fail_fast: spec.fail_fast, max_parallel: spec.max_parallel, instances })
+    }
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn spec(matrix: Value) -> MatrixSpec {
+        MatrixSpec::from_config(&json!({ "matrix": matrix })).unwrap().unwrap()
+    }
+
+    fn combo(pairs: &[(&str, &str)]) -> Combination {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_expand_product_with_include_exclude() {
+        let matrix = spec(json!({
+            "node": [18, 20],
+            "os": ["linux", "windows"],
+            "exclude": [{ "node": 18, "os": "windows" }],
+            "include": [
+                { "node": 20, "experimental": true },
+                { "node": 22, "os": "linux" },
+            ],
+        }));
+        assert!(matrix.fail_fast);
+        assert_eq!(
+            matrix.expand().unwrap(),
+            vec![
+                combo(&[("node", "18"), ("os", "linux")]),
+                combo(&[("experimental", "true"), ("node", "20"), ("os", "linux")]),
+                combo(&[("experimental", "true"), ("node", "20"), ("os", "windows")]),
+                combo(&[("node", "22"), ("os", "linux")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_config() {
+        assert!(MatrixSpec::from_config(&json!({})).unwrap().is_none());
+        assert!(MatrixSpec::from_config(&json!({ "matrix": "" })).unwrap().is_none());
+        assert!(MatrixSpec::from_config(&json!({ "matrix": {} })).unwrap().is_none());
+
+        let typed = MatrixSpec::from_config(&json!({ "matrix": r#"{"os": ["linux"], "failFast": false, "maxParallel": 2}"# }))
+            .unwrap()
+            .unwrap();
+        assert!(!typed.fail_fast);
+        assert_eq!(typed.max_parallel, Some(2));
+        assert_eq!(typed.expand().unwrap(), vec![combo(&[("os", "linux")])]);
+
+        assert!(MatrixSpec::from_config(&json!({ "matrix": "{not json" })).is_err());
+        assert!(MatrixSpec::from_config(&json!({ "matrix": { "os": "linux" } })).is_err());
+        assert!(spec(json!({ "os": [] })).expand().is_err());
+        assert!(spec(json!({ "os": [["nested"]] })).expand().is_err());
+        assert!(spec(json!({ "os": ["linux"], "exclude": [{ "os": "linux" }] })).expand().is_err());
+        let wide: Vec<u32> = (0..20).collect();
+        assert!(spec(json!({ "a": wide, "b": wide })).expand().unwrap_err().contains("more than"));
+    }
+
+    #[test]
+    fn test_instance_config() {
+        let config = json!({
+            "command": "npm test",
+            "envVars": { "CI": "1" },
+            "executionProfile": { "dockerImage": "node:${{ matrix.node-version }}" },
+            "matrix": { "node-version": [20] },
+        });
+        let plan = MatrixPlan::new("test", &config, &MatrixSpec::from_config(&config).unwrap().unwrap()).unwrap();
+        let instance = &plan.instances[0];
+        assert_eq!(instance.id, "test[0]");
+        assert!(instance.config.get("matrix").is_none());
+        assert_eq!(instance.config["executionProfile"]["dockerImage"], "node:20");
+        assert_eq!(instance.config["envVars"], json!({ "CI": "1", "MATRIX_NODE_VERSION": "20" }));
+        assert_eq!(describe(&instance.combination), "node-version=20");
+    }
+}
@@ -0,0 +1,310 @@
+// ============================================================
+// DevFlow Studio — Process-Tree Resource Metrics
+// Aggregates CPU, RSS, threads and disk I/O across a node's
+// whole process tree (shell → npm → node, make → cc, …) and
+// keeps a bounded time series for the analytics panel.
+// A single background sampler scans the process table once per
+// tick and serves every running node.
+// ============================================================
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use sysinfo::{Pid, ProcessRefreshKind, System};
+use tokio::sync::mpsc;
+
+/// How often the shared sampler scans the process table.
+pub const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Most samples kept per execution; older points are thinned out beyond this.
+pub const MAX_SAMPLES: usize = 600;
+
+/// One point in a node's resource time series, summed over its process tree.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ResourceSample {
+    /// Milliseconds since the node started.
+    pub elapsed_ms: u64,
+    pub cpu_usage: f32,
+    pub memory_mb: u64,
+    pub threads: u32,
+    pub processes: u32,
+    /// Bytes read/written by the tree since the previous sample.
+    pub disk_read_bytes: u64,
+    pub disk_written_bytes: u64,
+}
+
+/// Sum resource usage over `root` and all of its live descendants.
+/// Returns `None` once the root process has disappeared.
+pub fn sample_tree(sys: &System, root: Pid, elapsed_ms: u64) -> Option<ResourceSample> {
+    sys.process(root)?;
+
+    let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+    for (pid, process) in sys.processes() {
+        // Linux lists threads as pseudo-processes; they're counted via `tasks()`
+        if process.thread_kind().is_some() {
+            continue;
+        }
+        if let Some(parent) = process.parent() {
+            children.entry(parent).or_default().push(*pid);
+        }
+    }
+
+    let mut sample = ResourceSample { elapsed_ms, ..Default::default() };
+    let mut memory_bytes = 0u64;
+    let mut stack = vec![root];
+
+    while let Some(pid) = stack.pop() {
+        let Some(process) = sys.process(pid) else { continue };
+        let disk = process.disk_usage();
+
+        sample.processes += 1;
+        sample.cpu_usage += process.cpu_usage();
+        sample.threads += process.tasks().map_or(1, |t| t.len().max(1) as u32);
+        sample.disk_read_bytes += disk.read_bytes;
+        sample.disk_written_bytes += disk.written_bytes;
+        memory_bytes += process.memory();
+
+        if let Some(kids) = children.get(&pid) {
+            stack.extend(kids);
+        }
+    }
+
+    sample.memory_mb = memory_bytes / 1024 / 1024;
+    Some(sample)
+}
+
+/// Bounded time series plus peaks and I/O totals for one execution.
+#[derive(Debug, Default)]
+pub struct ResourceSeries {
+    samples: Vec<ResourceSample>,
+    /// Keep every `stride`-th sample once the series has been thinned.
+    stride: usize,
+    seen: usize,
+    pub max_cpu: f32,
+    pub max_memory_mb: u64,
+    pub max_threads: u32,
+    pub disk_read_bytes: u64,
+    pub disk_written_bytes: u64,
+}
+
+impl ResourceSeries {
+    pub fn push(&mut self, sample: ResourceSample) {
+        self.max_cpu = self.max_cpu.max(sample.cpu_usage);
+        self.max_memory_mb = self.max_memory_mb.max(sample.memory_mb);
+        self.max_threads = self.max_threads.max(sample.threads);
+        self.disk_read_bytes += sample.disk_read_bytes;
+        self.disk_written_bytes += sample.disk_written_bytes;
+
+        let stride = self.stride.max(1);
+        self.seen += 1;
+        if self.seen % stride != 0 {
+            return;
+        }
+
+        self.samples.push(sample);
+        if self.samples.len() > MAX_SAMPLES {
+            // Halve the resolution: drop every other point and sample half as often
+            let mut i = 0;
+            self.samples.retain(|_| {
+                i += 1;
+                i % 2 == 0
+            });
+            self.stride = stride * 2;
+        }
+    }
+
+    pub fn into_samples(self) -> Vec<ResourceSample> {
+        self.samples
+    }
+}
+
+// ── Shared sampler service ────────────────────────────────────────────────────
+
+/// A process tree the sampler is reporting on.
+struct Watch {
+    root: Pid,
+    started: Instant,
+    tx: mpsc::Sender<ResourceSample>,
+}
+
+#[derive(Default)]
+struct SamplerInner {
+    next_id: u64,
+    watches: HashMap<u64, Watch>,
+    running: bool,
+}
+
+/// One background thread that refreshes `sysinfo` once per tick for all
+/// running nodes, managed as Tauri state. The thread only runs while at
+/// least one execution is being watched.
+#[derive(Default)]
+pub struct MetricsSampler {
+    inner: Arc<Mutex<SamplerInner>>,
+}
+
+/// Receives samples for one process tree. Dropping it stops the watch.
+pub struct SamplerSubscription {
+    id: u64,
+    rx: mpsc::Receiver<ResourceSample>,
+    inner: Arc<Mutex<SamplerInner>>,
+}
+
+impl MetricsSampler {
+    /// Start reporting on the process tree rooted at `pid`.
+    pub fn watch(&self, pid: u32) -> SamplerSubscription {
+        let (tx, rx) = mpsc::channel(8);
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_id += 1;
+        let id = inner.next_id;
+        inner.watches.insert(id, Watch { root: Pid::from_u32(pid), started: Instant::now(), tx });
+
+        if !inner.running {
+            inner.running = true;
+            let shared = self.inner.clone();
+            std::thread::Builder::new()
+                .name("devflow-metrics".into())
+                .spawn(move || sampler_loop(shared))
+                .expect("failed to spawn metrics sampler thread");
+        }
+
+        SamplerSubscription { id, rx, inner: self.inner.clone() }
+    }
+}
+
+impl SamplerSubscription {
+    pub async fn recv(&mut self) -> Option<ResourceSample> {
+        self.rx.recv().await
+    }
+}
+
+impl Drop for SamplerSubscription {
+    fn drop(&mut self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.watches.remove(&self.id);
+        }
+    }
+}
+
+fn sampler_loop(shared: Arc<Mutex<SamplerInner>>) {
+    let mut sys = System::new();
+    let refresh = ProcessRefreshKind::new().with_cpu().with_memory().with_disk_usage();
+
+    loop {
+        let roots: Vec<(u64, Pid, Instant)> = {
+            let mut inner = shared.lock().unwrap();
+            if inner.watches.is_empty() {
+                inner.running = false;
+                return;
+            }
+            inner.watches.iter().map(|(id, w)| (*id, w.root, w.started)).collect()
+        };
+
+        // One process-table scan serves every watched tree
+        sys.refresh_processes_specifics(refresh);
+
+        {
+            let mut inner = shared.lock().unwrap();
+            for (id, root, started) in roots {
+                let elapsed_ms = started.elapsed().as_millis() as u64;
+                let Some(sample) = sample_tree(&sys, root, elapsed_ms) else { continue };
+                let closed = match inner.watches.get(&id) {
+                    Some(watch) => matches!(
+                        watch.tx.try_send(sample),
+                        Err(mpsc::error::TrySendError::Closed(_))
+                    ),
+                    None => false,
+                };
+                if closed {
+                    inner.watches.remove(&id);
+                }
+            }
+        }
+
+        std::thread::sleep(SAMPLE_INTERVAL);
+    }
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(ms: u64, cpu: f32, mem: u64) -> ResourceSample {
+        ResourceSample {
+            elapsed_ms: ms,
+            cpu_usage: cpu,
+            memory_mb: mem,
+            threads: 2,
+            processes: 1,
+            disk_read_bytes: 10,
+            disk_written_bytes: 5,
+        }
+    }
+
+    #[test]
+    fn test_series_tracks_peaks_and_totals() {
+        let mut series = ResourceSeries::default();
+        series.push(sample(0, 10.0, 100));
+        series.push(sample(500, 80.0, 50));
+        series.push(sample(1000, 20.0, 300));
+        assert_eq!(series.max_cpu, 80.0);
+        assert_eq!(series.max_memory_mb, 300);
+        assert_eq!(series.max_threads, 2);
+        assert_eq!(series.disk_read_bytes, 30);
+        assert_eq!(series.disk_written_bytes, 15);
+        assert_eq!(series.into_samples().len(), 3);
+    }
+
+    #[test]
+    fn test_series_is_bounded() {
+        let mut series = ResourceSeries::default();
+        for i in 0..(MAX_SAMPLES as u64 * 5) {
+            series.push(sample(i * 500, 1.0, 1));
+        }
+        let samples = series.into_samples();
+        assert!(samples.len() <= MAX_SAMPLES);
+        assert!(samples.windows(2).all(|w| w[0].elapsed_ms < w[1].elapsed_ms));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_sampler_serves_watch_and_stops_when_idle() {
+        let mut child = std::process::Command::new("sleep").arg("5").spawn().expect("spawn sleep");
+        let sampler = MetricsSampler::default();
+
+        let mut sub = sampler.watch(child.id());
+        let sample = tokio::time::timeout(Duration::from_secs(5), sub.recv())
+            .await
+            .expect("sample within timeout")
+            .expect("sampler running");
+        assert_eq!(sample.processes, 1);
+
+        drop(sub);
+        let _ = child.kill();
+        let _ = child.wait();
+        tokio::time::sleep(SAMPLE_INTERVAL * 3).await;
+        assert!(!sampler.inner.lock().unwrap().running);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sample_tree_includes_descendants() {
+        let mut child = std::process::Command::new("sh")
+            .arg("-c")
+            .arg("sleep 5 & sleep 5 & wait")
+            .spawn()
+            .expect("spawn sh");
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        let mut sys = System::new();
+        sys.refresh_processes();
+        let usage = sample_tree(&sys, Pid::from_u32(child.id()), 0).expect("root present");
+        assert!(usage.processes >= 3, "expected sh + 2 sleeps, got {}", usage.processes);
+
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
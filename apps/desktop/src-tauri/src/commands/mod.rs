@@ -1,4 +1,23 @@
 pub mod executor;
+pub mod expression;
+pub mod docker;
+pub mod flow_engine;
+pub mod limits;
+pub mod log_event;
+pub mod matrix;
+pub mod metrics;
+pub mod node_outputs;
+pub mod node_config;
+pub mod output;
+pub mod process;
+pub mod redact;
+pub mod retry;
+pub mod sandbox;
+pub mod secret_file;
+pub mod shell;
+pub mod ssh;
+pub mod template;
+pub mod termination;
 pub mod detector;
 pub mod folders;
 pub mod secure_storage;
@@ -0,0 +1,364 @@
+// ============================================================
+// DevFlow Studio — Typed Node Configs
+// One variant per built-in command node. Each produces an argv
+// vector that is spawned directly, with no shell in between, so
+// a branch or tag containing spaces or `;` stays one argument.
+// ============================================================
+
+use serde::de::{DeserializeOwned, Deserializer};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Config of a node that runs a fixed program, parsed from `FlowNode.config`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeConfig {
+    GitPull(GitPullConfig),
+    DockerBuild(DockerBuildConfig),
+    DockerRun(DockerRunConfig),
+    DockerCompose(DockerComposeConfig),
+    NpmRun(NpmRunConfig),
+    PipInstall(PipInstallConfig),
+    MakeTarget(MakeTargetConfig),
+    KubectlApply(KubectlApplyConfig),
+    TestRunner(TestRunnerConfig),
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitPullConfig {
+    #[serde(default, deserialize_with = "non_empty")]
+    pub remote: Option<String>,
+    #[serde(default, deserialize_with = "non_empty")]
+    pub branch: Option<String>,
+    #[serde(default, deserialize_with = "non_empty")]
+    pub directory: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DockerBuildConfig {
+    #[serde(default, deserialize_with = "non_empty")]
+    pub context: Option<String>,
+    #[serde(default, deserialize_with = "non_empty")]
+    pub tag: Option<String>,
+    #[serde(default, deserialize_with = "non_empty")]
+    pub dockerfile: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DockerRunConfig {
+    #[serde(default, deserialize_with = "non_empty")]
+    pub image: Option<String>,
+    /// `host:container` mappings; the UI stores either a list or a comma-separated string.
+    #[serde(default, deserialize_with = "string_list")]
+    pub ports: Vec<String>,
+    #[serde(default, deserialize_with = "non_empty")]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub detach: bool,
+    #[serde(default)]
+    pub remove: bool,
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DockerComposeConfig {
+    #[serde(default, deserialize_with = "non_empty")]
+    pub action: Option<String>,
+    #[serde(default, deserialize_with = "non_empty")]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub detach: bool,
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NpmRunConfig {
+    #[serde(default, deserialize_with = "non_empty")]
+    pub script: Option<String>,
+    #[serde(default, deserialize_with = "non_empty")]
+    pub package_dir: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PipInstallConfig {
+    #[serde(default, deserialize_with = "non_empty")]
+    pub requirements: Option<String>,
+    #[serde(default)]
+    pub venv: bool,
+    #[serde(default, deserialize_with = "non_empty")]
+    pub venv_dir: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MakeTargetConfig {
+    #[serde(default, deserialize_with = "non_empty")]
+    pub target: Option<String>,
+    #[serde(default, deserialize_with = "non_empty")]
+    pub jobs: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KubectlApplyConfig {
+    #[serde(default, deserialize_with = "non_empty")]
+    pub manifest: Option<String>,
+    #[serde(default, deserialize_with = "non_empty")]
+    pub namespace: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TestRunnerConfig {
+    #[serde(default, deserialize_with = "non_empty")]
+    pub framework: Option<String>,
+    /// One test path or glob, passed as a single argument; not extra options.
+    #[serde(default, deserialize_with = "non_empty")]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub coverage: bool,
+}
+
+// ── Lenient field parsing ─────────────────────────────────────────────────────
+
+/// Text fields from the settings panel: `""` means unset, numbers are accepted as text.
+fn non_empty<'de, D: Deserializer<'de>>(de: D) -> Result<Option<String>, D::Error> {
+    Ok(match Value::deserialize(de)? {
+        Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
+/// A list of strings, or a single comma-separated string.
+fn string_list<'de, D: Deserializer<'de>>(de: D) -> Result<Vec<String>, D::Error> {
+    let items = match Value::deserialize(de)? {
+        Value::Array(items) => items.into_iter().filter_map(|v| v.as_str().map(str::to_string)).collect(),
+        Value::String(s) => s.split(',').map(str::to_string).collect(),
+        _ => Vec::new(),
+    };
+    Ok(items.into_iter().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+}
+
+/// Reject values that would be parsed as an option by the target program.
+fn positional<'a>(field: &str, value: &'a str) -> Result<&'a str, String> {
+    if value.starts_with('-') {
+        return Err(format!("Invalid {field} '{value}': must not start with '-'"));
+    }
+    Ok(value)
+}
+
+fn args(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|s| s.to_string()).collect()
+}
+
+// ── argv builders ─────────────────────────────────────────────────────────────
+
+impl NodeConfig {
+    /// Parse the config of a command node. Returns `Ok(None)` for node types that
+    /// don't map to a fixed program (scripts, delays, conditionals, …).
+    pub fn from_node(node_type: &str, config: &Value) -> Result<Option<Self>, String> {
+        fn parse<T: DeserializeOwned>(node_type: &str, config: &Value) -> Result<T, String> {
+            let config = if config.is_null() { Value::Object(Default::default()) } else { config.clone() };
+            serde_json::from_value(config).map_err(|e| format!("Invalid {node_type} config: {e}"))
+        }
+
+        Ok(Some(match node_type {
+            "gitPull" => NodeConfig::GitPull(parse(node_type, config)?),
+            "dockerBuild" => NodeConfig::DockerBuild(parse(node_type, config)?),
+            "dockerRun" => NodeConfig::DockerRun(parse(node_type, config)?),
+            "dockerCompose" => NodeConfig::DockerCompose(parse(node_type, config)?),
+            "npmRun" => NodeConfig::NpmRun(parse(node_type, config)?),
+            "pipInstall" => NodeConfig::PipInstall(parse(node_type, config)?),
+            "makeTarget" => NodeConfig::MakeTarget(parse(node_type, config)?),
+            "kubectlApply" => NodeConfig::KubectlApply(parse(node_type, config)?),
+            "testRunner" => NodeConfig::TestRunner(parse(node_type, config)?),
+            _ => return Ok(None),
+        }))
+    }
+
+    /// Program and arguments to spawn. `project_path` is the fallback
+    /// repository for `gitPull`, matching the old command template.
+    pub fn argv(&self, project_path: Option<&str>) -> Result<Vec<String>, String> {
+        let argv = match self {
+            NodeConfig::GitPull(c) => {
+                let dir = c.directory.as_deref().or(project_path).unwrap_or(".");
+                let remote = positional("remote", c.remote.as_deref().unwrap_or("origin"))?;
+                let branch = positional("branch", c.branch.as_deref().unwrap_or("main"))?;
+                args(&["git", "-C", dir, "pull", remote, branch])
+            }
+            NodeConfig::DockerBuild(c) => {
+                let tag = positional("tag", c.tag.as_deref().unwrap_or("myapp:latest"))?;
+                let mut argv = args(&["docker", "build", "-t", tag]);
+                // The default is implied by docker; only pass an explicit override
+                if let Some(file) = c.dockerfile.as_deref().filter(|f| *f != "Dockerfile") {
+                    argv.extend(args(&["-f", file]));
+                }
+                argv.extend(args(&["--", c.context.as_deref().unwrap_or(".")]));
+                argv
+            }
+            NodeConfig::DockerRun(c) => {
+                let mut argv = args(&["docker", "run"]);
+                if c.detach {
+                    argv.push("-d".into());
+                }
+                if c.remove {
+                    argv.push("--rm".into());
+                }
+                if let Some(ref name) = c.name {
+                    argv.extend(args(&["--name", name]));
+                }
+                let ports = if c.ports.is_empty() { vec!["3000:3000".to_string()] } else { c.ports.clone() };
+                for port in &ports {
+                    argv.extend(args(&["-p", port]));
+                }
+                argv.push(positional("image", c.image.as_deref().unwrap_or("myapp:latest"))?.to_string());
+                argv
+            }
+            NodeConfig::DockerCompose(c) => {
+                let action = positional("action", c.action.as_deref().unwrap_or("up"))?;
+                let mut argv = args(&["docker", "compose", "-f", c.file.as_deref().unwrap_or("docker-compose.yml"), action]);
+                if c.detach && action == "up" {
+                    argv.push("-d".into());
+                }
+                argv
+            }
+            NodeConfig::NpmRun(c) => {
+                let mut argv = args(&["npm", "run", positional("script", c.script.as_deref().unwrap_or("build"))?]);
+                if let Some(ref dir) = c.package_dir {
+                    argv.extend(args(&["--prefix", dir]));
+                }
+                argv
+            }
+            NodeConfig::PipInstall(c) => {
+                let requirements = c.requirements.as_deref().unwrap_or("requirements.txt");
+                if c.venv {
+                    // Two steps need a shell, but values are passed as positional
+                    // parameters ("$1", "$2") and never spliced into the script.
+                    let venv = c.venv_dir.as_deref().unwrap_or(".venv");
+                    args(&[
+                        "sh", "-c", r#"python -m venv "$1" && "$1/bin/pip" install -r "$2""#,
+                        "pip-install", venv, requirements,
+                    ])
+                } else {
+                    args(&["pip", "install", "-r", requirements])
+                }
+            }
+            NodeConfig::MakeTarget(c) => {
+                let jobs = c.jobs.as_deref().unwrap_or("4");
+                if jobs.parse::<u32>().is_err() {
+                    return Err(format!("Invalid jobs '{jobs}': must be a number"));
+                }
+                let target = positional("target", c.target.as_deref().unwrap_or("build"))?;
+                args(&["make", &format!("-j{jobs}"), target])
+            }
+            NodeConfig::KubectlApply(c) => {
+                let namespace = c.namespace.as_deref().unwrap_or("default");
+                let mut argv = args(&["kubectl", "apply", "-f", c.manifest.as_deref().unwrap_or("k8s/"), "-n", namespace]);
+                if c.dry_run {
+                    argv.push("--dry-run=client".into());
+                }
+                argv
+            }
+            NodeConfig::TestRunner(c) => {
+                let pattern = c.pattern.as_deref().map(|p| positional("pattern", p)).transpose()?;
+                match c.framework.as_deref().unwrap_or("jest") {
+                    "pytest" => {
+                        let mut argv = args(&["pytest"]);
+                        argv.extend(pattern.map(str::to_string));
+                        if c.coverage {
+                            argv.push("--cov".into());
+                        }
+                        argv
+                    }
+                    "go test" => args(&["go", "test", "./..."]),
+                    "cargo test" => args(&["cargo", "test"]),
+                    "vitest" => args(&["npx", "vitest", "run"]),
+                    other => {
+                        let mut argv = args(&["npx", positional("framework", other)?]);
+                        argv.extend(pattern.map(str::to_string));
+                        if c.coverage {
+                            argv.push("--coverage".into());
+                        }
+                        argv
+                    }
+                }
+            }
+        };
+        Ok(argv)
+    }
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn argv(node_type: &str, config: Value) -> Vec<String> {
+        NodeConfig::from_node(node_type, &config).unwrap().unwrap().argv(Some("/repo")).unwrap()
+    }
+
+    #[test]
+    fn test_git_pull_keeps_branch_as_one_argument() {
+        let out = argv("gitPull", json!({ "remote": "", "branch": "feat; rm -rf ~", "directory": "" }));
+        assert_eq!(out, vec!["git", "-C", "/repo", "pull", "origin", "feat; rm -rf ~"]);
+    }
+
+    #[test]
+    fn test_option_like_values_are_rejected() {
+        let node = NodeConfig::from_node("gitPull", &json!({ "branch": "--upload-pack=evil" })).unwrap().unwrap();
+        assert!(node.argv(None).is_err());
+    }
+
+    #[test]
+    fn test_docker_run_ports_from_string_or_list() {
+        let from_str = argv("dockerRun", json!({ "image": "app", "ports": "80:80, 443:443", "remove": true }));
+        let from_list = argv("dockerRun", json!({ "image": "app", "ports": ["80:80", "443:443"], "remove": true }));
+        assert_eq!(from_str, from_list);
+        assert_eq!(from_str, vec!["docker", "run", "--rm", "-p", "80:80", "-p", "443:443", "app"]);
+    }
+
+    #[test]
+    fn test_make_jobs_accepts_number_and_validates() {
+        assert_eq!(argv("makeTarget", json!({ "jobs": 8, "target": "all" })), vec!["make", "-j8", "all"]);
+        let node = NodeConfig::from_node("makeTarget", &json!({ "jobs": "4; reboot" })).unwrap().unwrap();
+        assert!(node.argv(None).is_err());
+    }
+
+    #[test]
+    fn test_pip_venv_passes_paths_as_parameters() {
+        let out = argv("pipInstall", json!({ "venv": true, "venvDir": "my env", "requirements": "req.txt" }));
+        assert_eq!(out[..2], ["sh", "-c"]);
+        assert_eq!(out[4..], ["my env", "req.txt"]);
+        assert!(!out[2].contains("my env"));
+    }
+
+    #[test]
+    fn test_test_runner_frameworks() {
+        assert_eq!(argv("testRunner", json!({ "framework": "pytest", "coverage": true })), vec!["pytest", "--cov"]);
+        assert_eq!(argv("testRunner", json!({ "framework": "go test" })), vec!["go", "test", "./..."]);
+        assert_eq!(
+            argv("testRunner", json!({ "framework": "jest", "pattern": "src/a b.test.ts" })),
+            vec!["npx", "jest", "src/a b.test.ts"]
+        );
+        for framework in ["pytest", "jest"] {
+            let node = NodeConfig::from_node("testRunner", &json!({ "framework": framework, "pattern": "-x" }))
+                .unwrap()
+                .unwrap();
+            assert!(node.argv(None).is_err());
+        }
+    }
+
+    #[test]
+    fn test_non_command_nodes_have_no_config() {
+        assert!(NodeConfig::from_node("scriptRun", &json!({ "command": "ls" })).unwrap().is_none());
+        assert!(NodeConfig::from_node("delayNode", &Value::Null).unwrap().is_none());
+    }
+}
@@ -0,0 +1,128 @@
+// ============================================================
+// DevFlow Studio — Node Outputs
+// Values a node publishes for downstream nodes, either as a
+// `::set-output name=<name>::<value>` line on stdout/stderr or
+// in the file named by $DEVFLOW_OUTPUT, one `name=value` per
+// line (or `name<<DELIM` … `DELIM` for multiline values).
+// ============================================================
+
+use std::collections::BTreeMap;
+
+use super::secret_file::SecretFile;
+
+/// Env var holding the path of the node's output file.
+pub const OUTPUT_ENV: &str = "DEVFLOW_OUTPUT";
+
+/// Where the output file appears inside a container.
+pub const CONTAINER_OUTPUT_PATH: &str = "/devflow/output";
+
+/// A node's published outputs, by name.
+pub type Outputs = BTreeMap<String, String>;
+
+/// Output names are restricted so they can appear in `${{ … }}` paths.
+pub fn valid_output_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Parse a `::set-output name=<name>::<value>` directive.
+pub fn parse_directive(line: &str) -> Option<(String, String)> {
+    let rest = line.trim_start().strip_prefix("::set-output name=")?;
+    let (name, value) = rest.split_once("::")?;
+    valid_output_name(name).then(|| (name.to_string(), value.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+/// Parse the contents of a `$DEVFLOW_OUTPUT` file.
+pub fn parse_output_file(contents: &str) -> Result<Outputs, String> {
+    let mut outputs = Outputs::new();
+    let mut lines = contents.lines();
+    while let Some(line) = lines.next() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some((name, delimiter)) = line.split_once("<<") {
+            let name = name.trim();
+            let delimiter = delimiter.trim();
+            if !valid_output_name(name) || delimiter.is_empty() {
+                return Err(format!("Invalid output line '{line}'"));
+            }
+            let mut value = Vec::new();
+            loop {
+                match lines.next() {
+                    Some(l) if l == delimiter => break,
+                    Some(l) => value.push(l),
+                    None => return Err(format!("Output '{name}' is missing its closing '{delimiter}'")),
+                }
+            }
+            outputs.insert(name.to_string(), value.join("\n"));
+        } else if let Some((name, value)) = line.split_once('=') {
+            let name = name.trim();
+            if !valid_output_name(name) {
+                return Err(format!("Invalid output name '{name}'"));
+            }
+            outputs.insert(name.to_string(), value.to_string());
+        } else {
+            return Err(format!("Invalid output line '{line}': expected name=value"));
+        }
+    }
+    Ok(outputs)
+}
+
+/// The per-execution output file. Private like other temp files, since
+/// outputs can carry tokens; removed on drop.
+pub struct OutputFile {
+    file: SecretFile,
+}
+
+impl OutputFile {
+    pub fn create() -> Result<Self, String> {
+        SecretFile::create("output", b"")
+            .map(|file| Self { file })
+            .map_err(|e| format!("Failed to create output file: {e}"))
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        self.file.path()
+    }
+
+    pub fn read(&self) -> Result<Outputs, String> {
+        let contents = std::fs::read_to_string(self.path()).map_err(|e| format!("Failed to read outputs: {e}"))?;
+        parse_output_file(&contents)
+    }
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_directive() {
+        assert_eq!(
+            parse_directive("::set-output name=digest::sha256:abc::def"),
+            Some(("digest".into(), "sha256:abc::def".into()))
+        );
+        assert_eq!(parse_directive("  ::set-output name=empty::"), Some(("empty".into(), String::new())));
+        assert_eq!(parse_directive("echo ::set-output name=x::1"), None);
+        assert_eq!(parse_directive("::set-output name=bad name::1"), None);
+    }
+
+    #[test]
+    fn test_parse_output_file() {
+        let outputs = parse_output_file("tag=v1.2\n\nnotes<<EOF\nline 1\nline 2\nEOF\nurl=https://x/?a=b\n").unwrap();
+        assert_eq!(outputs["tag"], "v1.2");
+        assert_eq!(outputs["notes"], "line 1\nline 2");
+        assert_eq!(outputs["url"], "https://x/?a=b");
+
+        assert!(parse_output_file("no equals sign").is_err());
+        assert!(parse_output_file("x<<END\nunterminated").is_err());
+        assert!(parse_output_file("a.b=1").is_err());
+    }
+
+    #[test]
+    fn test_output_file_roundtrip() {
+        let file = OutputFile::create().unwrap();
+        std::fs::write(file.path(), "version=3\n").unwrap();
+        assert_eq!(file.read().unwrap()["version"], "3");
+    }
+}
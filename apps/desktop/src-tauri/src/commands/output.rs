@@ -0,0 +1,382 @@
+// ============================================================
+// DevFlow Studio — Byte-safe Output Capture
+// Splits raw child stdout/stderr bytes into lines with lossy
+// UTF-8 decoding. `\r` overwrites (progress bars from docker,
+// pip, curl…) are reported separately instead of piling up
+// into one huge line. In-memory capture is bounded to a head
+// and tail; the full stream is spilled to a log file on disk.
+// ============================================================
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Longest line kept before it is force-flushed, so a child that never
+/// prints `\n` can't grow the buffer without bound.
+pub const MAX_LINE_BYTES: usize = 64 * 1024;
+
+const READ_CHUNK_BYTES: usize = 8 * 1024;
+
+/// A decoded unit of child output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputEvent {
+    /// A complete, newline-terminated line.
+    Line(String),
+    /// An intermediate state that the child overwrote with `\r`.
+    Progress(String),
+}
+
+/// Incremental splitter that turns arbitrary byte chunks into `OutputEvent`s.
+#[derive(Default)]
+pub struct LineSplitter {
+    buf: Vec<u8>,
+    pending_cr: bool,
+}
+
+impl LineSplitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk of raw bytes, appending any completed events to `out`.
+    pub fn feed(&mut self, bytes: &[u8], out: &mut Vec<OutputEvent>) {
+        for &b in bytes {
+            if self.pending_cr {
+                self.pending_cr = false;
+                if b == b'\n' {
+                    // `\r\n` is a plain line ending
+                    out.push(OutputEvent::Line(self.take()));
+                    continue;
+                }
+                // Lone `\r`: the current text is about to be overwritten
+                if !self.buf.is_empty() {
+                    out.push(OutputEvent::Progress(self.take()));
+                }
+            }
+
+            match b {
+                b'\n' => out.push(OutputEvent::Line(self.take())),
+                b'\r' => self.pending_cr = true,
+                _ => {
+                    self.buf.push(b);
+                    if self.buf.len() >= MAX_LINE_BYTES {
+                        out.push(OutputEvent::Line(self.take()));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Flush whatever is left once the stream has closed.
+    pub fn finish(&mut self, out: &mut Vec<OutputEvent>) {
+        if self.pending_cr || !self.buf.is_empty() {
+            self.pending_cr = false;
+            out.push(OutputEvent::Line(self.take()));
+        }
+    }
+
+    fn take(&mut self) -> String {
+        let text = String::from_utf8_lossy(&self.buf).into_owned();
+        self.buf.clear();
+        text
+    }
+}
+
+/// Drain `reader` to EOF, invoking `on_event` for every line or progress update.
+/// Never stops early on invalid UTF-8, so the child can't block on a full pipe.
+pub async fn read_output<R, F>(mut reader: R, mut on_event: F)
+where
+    R: AsyncRead + Unpin,
+    F: FnMut(OutputEvent),
+{
+    let mut splitter = LineSplitter::new();
+    let mut chunk = vec![0u8; READ_CHUNK_BYTES];
+    let mut events = Vec::new();
+
+    loop {
+        match reader.read(&mut chunk).await {
+            Ok(0) => break,
+            Ok(n) => splitter.feed(&chunk[..n], &mut events),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(_) => break,
+        }
+        events.drain(..).for_each(&mut on_event);
+    }
+
+    splitter.finish(&mut events);
+    events.drain(..).for_each(&mut on_event);
+}
+
+// ── Bounded in-memory capture ─────────────────────────────────────────────────
+
+/// Bytes of output kept from the start of each stream.
+pub const DEFAULT_HEAD_BYTES: usize = 64 * 1024;
+/// Bytes of output kept from the end of each stream.
+pub const DEFAULT_TAIL_BYTES: usize = 64 * 1024;
+
+/// Keeps the first and last lines of a stream within a fixed byte budget,
+/// counting whatever falls in between.
+pub struct BoundedBuffer {
+    head: Vec<String>,
+    head_bytes: usize,
+    head_limit: usize,
+    tail: VecDeque<String>,
+    tail_bytes: usize,
+    tail_limit: usize,
+    omitted_lines: usize,
+}
+
+impl Default for BoundedBuffer {
+    fn default() -> Self {
+        Self::new(DEFAULT_HEAD_BYTES, DEFAULT_TAIL_BYTES)
+    }
+}
+
+impl BoundedBuffer {
+    pub fn new(head_limit: usize, tail_limit: usize) -> Self {
+        Self {
+            head: Vec::new(),
+            head_bytes: 0,
+            head_limit,
+            tail: VecDeque::new(),
+            tail_bytes: 0,
+            tail_limit,
+            omitted_lines: 0,
+        }
+    }
+
+    pub fn push(&mut self, line: String) {
+        if self.tail.is_empty() && self.head_bytes + line.len() <= self.head_limit {
+            self.head_bytes += line.len();
+            self.head.push(line);
+            return;
+        }
+
+        self.tail_bytes += line.len();
+        self.tail.push_back(line);
+        while self.tail_bytes > self.tail_limit {
+            match self.tail.pop_front() {
+                Some(dropped) => {
+                    self.tail_bytes -= dropped.len();
+                    self.omitted_lines += 1;
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Whether any lines were dropped from the middle of the stream.
+    pub fn is_truncated(&self) -> bool {
+        self.omitted_lines > 0
+    }
+
+    /// Join head and tail, marking where lines were omitted. The marker only
+    /// points at the log file when one was actually written.
+    pub fn into_text(self, has_log_file: bool) -> String {
+        let mut parts = self.head;
+        if self.omitted_lines > 0 {
+            let hint = if has_log_file { " (full output in log file)" } else { "" };
+            parts.push(format!("… {} lines omitted{hint} …", self.omitted_lines));
+        }
+        parts.extend(self.tail);
+        parts.join("\n")
+    }
+}
+
+// ── Spill-to-disk log file ────────────────────────────────────────────────────
+
+/// Full, untruncated output of one execution, written by a background task.
+pub struct LogSpill {
+    path: PathBuf,
+    tx: mpsc::UnboundedSender<String>,
+    task: JoinHandle<std::io::Result<()>>,
+}
+
+/// Cloneable handle the stream readers use to append to a `LogSpill`.
+#[derive(Clone)]
+pub struct LogSpillWriter {
+    tx: mpsc::UnboundedSender<String>,
+}
+
+impl LogSpill {
+    /// Open (appending to) the log file at `path`, creating parent directories.
+    pub async fn create(path: &Path) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+        let task = tokio::spawn(async move {
+            let mut out = BufWriter::new(file);
+            while let Some(line) = rx.recv().await {
+                out.write_all(line.as_bytes()).await?;
+                out.write_all(b"\n").await?;
+            }
+            out.flush().await
+        });
+
+        Ok(Self { path: path.to_path_buf(), tx, task })
+    }
+
+    pub fn writer(&self) -> LogSpillWriter {
+        LogSpillWriter { tx: self.tx.clone() }
+    }
+
+    /// Flush and close the file once every writer has been dropped.
+    pub async fn close(self) -> std::io::Result<PathBuf> {
+        drop(self.tx);
+        self.task.await.map_err(std::io::Error::other)??;
+        Ok(self.path)
+    }
+}
+
+impl LogSpillWriter {
+    /// Append one line tagged with its stream name.
+    pub fn write(&self, stream: &str, line: &str) {
+        let _ = self.tx.send(format!("[{stream}] {line}"));
+    }
+}
+
+/// Restrict an identifier to `[A-Za-z0-9_-]`, so it can't name `.`/`..` or a
+/// nested path.
+pub fn sanitize_file_component(raw: &str) -> String {
+    raw.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(chunks: &[&[u8]]) -> Vec<OutputEvent> {
+        let mut splitter = LineSplitter::new();
+        let mut out = Vec::new();
+        for chunk in chunks {
+            splitter.feed(chunk, &mut out);
+        }
+        splitter.finish(&mut out);
+        out
+    }
+
+    #[test]
+    fn test_splits_lines_and_crlf() {
+        let out = split(&[b"one\ntwo\r\nthree"]);
+        assert_eq!(out, vec![
+            OutputEvent::Line("one".into()),
+            OutputEvent::Line("two".into()),
+            OutputEvent::Line("three".into()),
+        ]);
+    }
+
+    #[test]
+    fn test_carriage_return_becomes_progress() {
+        let out = split(&[b"\r 10%\r 50%", b"\r100%\ndone\n"]);
+        assert_eq!(out, vec![
+            OutputEvent::Progress(" 10%".into()),
+            OutputEvent::Progress(" 50%".into()),
+            OutputEvent::Line("100%".into()),
+            OutputEvent::Line("done".into()),
+        ]);
+    }
+
+    #[test]
+    fn test_crlf_split_across_chunks() {
+        let out = split(&[b"line\r", b"\nnext\n"]);
+        assert_eq!(out, vec![
+            OutputEvent::Line("line".into()),
+            OutputEvent::Line("next".into()),
+        ]);
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_lossy_and_does_not_stop() {
+        let out = split(&[b"ok\n\xff\xfebad\nafter\n"]);
+        assert_eq!(out.len(), 3);
+        assert_eq!(out[1], OutputEvent::Line("\u{FFFD}\u{FFFD}bad".into()));
+        assert_eq!(out[2], OutputEvent::Line("after".into()));
+    }
+
+    #[test]
+    fn test_overlong_line_is_flushed() {
+        let long = vec![b'x'; MAX_LINE_BYTES + 10];
+        let out = split(&[&long]);
+        assert_eq!(out.len(), 2);
+        assert!(matches!(&out[0], OutputEvent::Line(l) if l.len() == MAX_LINE_BYTES));
+    }
+
+    #[test]
+    fn test_bounded_buffer_keeps_head_and_tail() {
+        let mut buf = BoundedBuffer::new(10, 10);
+        for i in 0..100 {
+            buf.push(format!("l{i:02}"));
+        }
+        assert!(buf.is_truncated());
+        let text = buf.into_text(true);
+        assert!(text.starts_with("l00\nl01"));
+        assert!(text.ends_with("l98\nl99"));
+        assert!(text.contains("lines omitted (full output in log file)"));
+    }
+
+    #[test]
+    fn test_bounded_buffer_marker_without_log_file() {
+        let mut buf = BoundedBuffer::new(10, 10);
+        for i in 0..100 {
+            buf.push(format!("l{i:02}"));
+        }
+        let text = buf.into_text(false);
+        assert!(text.contains("lines omitted …"));
+        assert!(!text.contains("log file"));
+    }
+
+    #[test]
+    fn test_bounded_buffer_small_output_untouched() {
+        let mut buf = BoundedBuffer::default();
+        buf.push("hello".into());
+        buf.push("world".into());
+        assert!(!buf.is_truncated());
+        assert_eq!(buf.into_text(true), "hello\nworld");
+    }
+
+    #[test]
+    fn test_sanitize_file_component() {
+        assert_eq!(sanitize_file_component("dockerBuild-17"), "dockerBuild-17");
+        assert_eq!(sanitize_file_component("../etc/passwd"), "___etc_passwd");
+        assert_eq!(sanitize_file_component(".."), "__");
+        assert_eq!(sanitize_file_component("v1.2"), "v1_2");
+    }
+
+    #[tokio::test]
+    async fn test_log_spill_writes_all_lines() {
+        let path = std::env::temp_dir()
+            .join(format!("devflow-spill-test-{}", std::process::id()))
+            .join("node.log");
+        let spill = LogSpill::create(&path).await.unwrap();
+        let writer = spill.writer();
+        writer.write("stdout", "first");
+        writer.write("stderr", "second");
+        drop(writer);
+
+        let path = spill.close().await.unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "[stdout] first\n[stderr] second\n");
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_read_output_drains_reader() {
+        let data: &[u8] = b"a\nb\rc\n";
+        let mut events = Vec::new();
+        read_output(data, |e| events.push(e)).await;
+        assert_eq!(events.len(), 3);
+    }
+}
@@ -0,0 +1,271 @@
+// ============================================================
+// DevFlow Studio — Process Registry & Tree Termination
+// Tracks in-flight `execute_command` children in shared app state
+// so they can be cancelled by node ID or run ID, and kills whole
+// process groups (SIGTERM → grace period → SIGKILL).
+// ============================================================
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::process::{Child, Command};
+use tokio::sync::{watch, Notify};
+
+use super::executor::CommandResult;
+
+/// A child process currently being driven by `execute_command`.
+struct RunningProcess {
+    node_id: String,
+    run_id: Option<String>,
+    cancel: Arc<Notify>,
+    cancelled: Arc<AtomicBool>,
+    finished: watch::Receiver<Option<CommandResult>>,
+}
+
+#[derive(Default)]
+struct RegistryInner {
+    next_id: u64,
+    running: HashMap<u64, RunningProcess>,
+}
+
+/// Shared registry of running executions, managed as Tauri state.
+#[derive(Default)]
+pub struct ProcessRegistry {
+    inner: Arc<Mutex<RegistryInner>>,
+}
+
+/// Handle held by `execute_command` for the lifetime of one child, from
+/// before it is spawned. Dropping it removes the entry from the registry.
+pub struct Registration {
+    id: u64,
+    pub cancel: Arc<Notify>,
+    cancelled: Arc<AtomicBool>,
+    finished: watch::Sender<Option<CommandResult>>,
+    inner: Arc<Mutex<RegistryInner>>,
+}
+
+impl ProcessRegistry {
+    /// Register a new execution for `node_id` (optionally part of `run_id`).
+    pub fn register(&self, node_id: &str, run_id: Option<&str>) -> Registration {
+        let cancel = Arc::new(Notify::new());
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (finished_tx, finished_rx) = watch::channel(None);
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_id += 1;
+        let id = inner.next_id;
+        inner.running.insert(id, RunningProcess {
+            node_id: node_id.to_string(),
+            run_id: run_id.map(str::to_string),
+            cancel: cancel.clone(),
+            cancelled: cancelled.clone(),
+            finished: finished_rx,
+        });
+
+        Registration { id, cancel, cancelled, finished: finished_tx, inner: self.inner.clone() }
+    }
+
+    /// Signal cancellation to every execution matching `node_id` and/or `run_id`.
+    /// Returns receivers that resolve to each execution's final result.
+    pub fn cancel(
+        &self,
+        node_id: Option<&str>,
+        run_id: Option<&str>,
+    ) -> Vec<watch::Receiver<Option<CommandResult>>> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .running
+            .values()
+            .filter(|p| node_id.map_or(true, |n| p.node_id == n))
+            .filter(|p| run_id.map_or(true, |r| p.run_id.as_deref() == Some(r)))
+            .map(|p| {
+                p.cancelled.store(true, Ordering::SeqCst);
+                p.cancel.notify_one();
+                p.finished.clone()
+            })
+            .collect()
+    }
+
+    /// Number of executions currently in flight.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().running.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Registration {
+    /// Whether a cancellation arrived, even one with nobody waiting for it yet.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Publish the final result to anyone waiting on a cancellation.
+    pub fn finish(&self, result: &CommandResult) {
+        self.finished.send_replace(Some(result.clone()));
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.running.remove(&self.id);
+        }
+    }
+}
+
+/// Wait for a cancelled execution to publish its result.
+/// Returns `None` if the execution ended without producing one.
+pub async fn wait_finished(mut rx: watch::Receiver<Option<CommandResult>>) -> Option<CommandResult> {
+    loop {
+        if let Some(result) = rx.borrow_and_update().clone() {
+            return Some(result);
+        }
+        if rx.changed().await.is_err() {
+            return rx.borrow().clone();
+        }
+    }
+}
+
+// ── Process-tree termination ──────────────────────────────────────────────────
+
+/// Default time between SIGTERM and SIGKILL when stopping a node.
+pub const DEFAULT_KILL_GRACE_SECS: u64 = 5;
+
+/// Spawn the child as the leader of a new process group, so that
+/// grandchildren (`docker run`, `npm` → `node`, `make -j` jobs) can be
+/// signalled together with the `$SHELL -c` wrapper.
+pub fn isolate_process_group(cmd: &mut Command) {
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    #[cfg(windows)]
+    {
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        cmd.creation_flags(CREATE_NEW_PROCESS_GROUP);
+    }
+}
+
+/// Terminate the child's whole process group.
+/// Sends SIGTERM, waits up to `grace` for the group to exit, then SIGKILLs
+/// whatever is left. Returns the signal that finally ended the tree, or
+/// `None` if it had already exited on its own.
+#[cfg(unix)]
+pub async fn terminate_tree(child: &mut Child, pgid: Option<u32>, grace: Duration) -> Option<String> {
+    let Some(pgid) = pgid else {
+        let _ = child.kill().await;
+        return Some("SIGKILL".to_string());
+    };
+    let group = -(pgid as libc::pid_t);
+
+    if !signal_group(group, libc::SIGTERM) {
+        let _ = child.wait().await;
+        return None;
+    }
+
+    let deadline = Instant::now() + grace;
+    while Instant::now() < deadline {
+        // Reap the leader so it doesn't linger as a zombie group member.
+        let _ = child.try_wait();
+        if !signal_group(group, 0) {
+            return Some("SIGTERM".to_string());
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    signal_group(group, libc::SIGKILL);
+    let _ = child.wait().await;
+    Some("SIGKILL".to_string())
+}
+
+/// Windows has no process groups to signal; `taskkill /T` walks the tree.
+#[cfg(windows)]
+pub async fn terminate_tree(child: &mut Child, pgid: Option<u32>, _grace: Duration) -> Option<String> {
+    if let Some(pid) = pgid {
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .output()
+            .await;
+    }
+    let _ = child.kill().await;
+    Some("TerminateProcess".to_string())
+}
+
+/// Send `sig` to a process group. Returns `false` once the group no longer exists.
+#[cfg(unix)]
+fn signal_group(group: libc::pid_t, sig: libc::c_int) -> bool {
+    // SAFETY: kill(2) has no memory-safety preconditions.
+    unsafe { libc::kill(group, sig) == 0 }
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_and_drop_removes_entry() {
+        let registry = ProcessRegistry::default();
+        let reg = registry.register("node-1", Some("run-1"));
+        assert_eq!(registry.len(), 1);
+        drop(reg);
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn test_cancel_filters_by_node_and_run() {
+        let registry = ProcessRegistry::default();
+        let _a = registry.register("build", Some("run-1"));
+        let _b = registry.register("test", Some("run-1"));
+        let _c = registry.register("build", Some("run-2"));
+
+        assert_eq!(registry.cancel(Some("build"), None).len(), 2);
+        assert_eq!(registry.cancel(None, Some("run-1")).len(), 2);
+        assert_eq!(registry.cancel(Some("build"), Some("run-2")).len(), 1);
+        assert_eq!(registry.cancel(Some("deploy"), None).len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_before_spawn_is_remembered() {
+        let registry = ProcessRegistry::default();
+        let reg = registry.register("build", Some("run-1"));
+        assert!(!reg.is_cancelled());
+        // Nobody is waiting yet, as while a node is still being set up
+        registry.cancel(Some("build"), None);
+        assert!(reg.is_cancelled());
+        tokio::time::timeout(Duration::from_secs(1), reg.cancel.notified()).await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_terminate_tree_kills_grandchildren() {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("sleep 30 & sleep 30 & wait");
+        isolate_process_group(&mut cmd);
+        let mut child = cmd.spawn().expect("spawn sh");
+        let pgid = child.id();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let signal = terminate_tree(&mut child, pgid, Duration::from_secs(2)).await;
+        assert_eq!(signal.as_deref(), Some("SIGTERM"));
+        assert!(!signal_group(-(pgid.unwrap() as libc::pid_t), 0));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_terminate_tree_escalates_to_sigkill() {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("trap '' TERM; sleep 30 & wait");
+        isolate_process_group(&mut cmd);
+        let mut child = cmd.spawn().expect("spawn sh");
+        let pgid = child.id();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let signal = terminate_tree(&mut child, pgid, Duration::from_millis(300)).await;
+        assert_eq!(signal.as_deref(), Some("SIGKILL"));
+    }
+}
@@ -0,0 +1,173 @@
+// ============================================================
+// DevFlow Studio — Secret Redaction
+// Masks injected secret values in child output before it is
+// streamed, spilled to disk or returned. Besides the raw value,
+// base64 (standard / URL-safe, any alignment) and URL-encoded
+// forms are masked, since scripts often echo those instead.
+// ============================================================
+
+use std::borrow::Cow;
+
+use base64::engine::general_purpose::{STANDARD_NO_PAD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use zeroize::Zeroizing;
+
+/// Replacement text for a masked secret.
+pub const MASK: &str = "***";
+
+/// Secrets shorter than this are not masked: they'd blank out ordinary output.
+pub const MIN_SECRET_LEN: usize = 4;
+
+/// Shortest derived (encoded) form worth masking on its own.
+const MIN_DERIVED_LEN: usize = 6;
+
+/// Characters JavaScript's `encodeURIComponent` leaves alone.
+const URI_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-').remove(b'_').remove(b'.').remove(b'!').remove(b'~')
+    .remove(b'*').remove(b'\'').remove(b'(').remove(b')');
+
+/// Masks every known form of a set of secret values.
+#[derive(Default)]
+pub struct Redactor {
+    /// Longest first, so a value is masked before any of its substrings.
+    needles: Vec<Zeroizing<String>>,
+}
+
+impl Redactor {
+    pub fn new<'a, I>(secrets: I) -> Self
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut needles: Vec<Zeroizing<String>> = Vec::new();
+        for secret in secrets {
+            if secret.len() < MIN_SECRET_LEN {
+                continue;
+            }
+            needles.push(Zeroizing::new(secret.to_string()));
+            needles.extend(encoded_forms(secret).into_iter().filter(|f| f.len() >= MIN_DERIVED_LEN));
+        }
+        needles.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        needles.dedup();
+        Self { needles }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.needles.is_empty()
+    }
+
+    /// Replace every occurrence of a secret (or an encoded form) with `MASK`.
+    pub fn redact<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for needle in &self.needles {
+            ranges.extend(text.match_indices(needle.as_str()).map(|(i, m)| (i, i + m.len())));
+        }
+        if ranges.is_empty() {
+            return Cow::Borrowed(text);
+        }
+
+        // Merge overlapping matches so each masked region is replaced once
+        ranges.sort_unstable();
+        let mut out = String::with_capacity(text.len());
+        let mut pos = 0;
+        for (start, end) in ranges {
+            if end <= pos {
+                continue;
+            }
+            if start >= pos {
+                out.push_str(&text[pos..start]);
+                out.push_str(MASK);
+            }
+            pos = end;
+        }
+        out.push_str(&text[pos..]);
+        Cow::Owned(out)
+    }
+}
+
+/// Base64 and URL-encoded forms of `secret`.
+fn encoded_forms(secret: &str) -> Vec<Zeroizing<String>> {
+    let mut forms = Vec::new();
+
+    // A secret embedded in a larger base64 payload (e.g. `user:token` in a
+    // Basic auth header) encodes differently depending on its byte offset
+    // mod 3. For each offset keep only the characters that depend solely on
+    // the secret's own bits.
+    for offset in 0..3usize {
+        let mut bytes = Zeroizing::new(vec![0u8; offset]);
+        bytes.extend_from_slice(secret.as_bytes());
+        let start = (offset * 8).div_ceil(6);
+        let end = bytes.len() * 8 / 6;
+        for engine in [&STANDARD_NO_PAD, &URL_SAFE_NO_PAD] {
+            let encoded = Zeroizing::new(engine.encode(&bytes[..]));
+            if start < end {
+                forms.push(Zeroizing::new(encoded[start..end].to_string()));
+            }
+        }
+    }
+
+    for set in [NON_ALPHANUMERIC, URI_COMPONENT] {
+        let encoded = utf8_percent_encode(secret, set).to_string();
+        if encoded != secret {
+            forms.push(Zeroizing::new(encoded));
+        }
+    }
+    forms
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::general_purpose::{STANDARD, URL_SAFE};
+
+    const SECRET: &str = "ghp_s3cr3t/T0ken+value?";
+
+    #[test]
+    fn test_masks_raw_value() {
+        let r = Redactor::new([SECRET]);
+        assert_eq!(r.redact(&format!("token={SECRET} done")), "token=*** done");
+    }
+
+    #[test]
+    fn test_masks_base64_forms() {
+        let r = Redactor::new([SECRET]);
+        for encoded in [STANDARD.encode(SECRET), URL_SAFE.encode(SECRET)] {
+            let line = format!("Authorization: Bearer {encoded}");
+            let out = r.redact(&line);
+            assert!(!out.contains(&encoded[..encoded.len() - 4]), "leaked: {out}");
+        }
+    }
+
+    #[test]
+    fn test_masks_base64_at_any_offset() {
+        let r = Redactor::new([SECRET]);
+        for user in ["a", "ab", "abc"] {
+            let header = STANDARD.encode(format!("{user}:{SECRET}"));
+            let out = r.redact(&header);
+            assert!(out.contains(MASK), "not masked for prefix {user:?}: {out}");
+        }
+    }
+
+    #[test]
+    fn test_masks_url_encoded_form() {
+        let r = Redactor::new([SECRET]);
+        let encoded = utf8_percent_encode(SECRET, URI_COMPONENT).to_string();
+        assert_eq!(r.redact(&format!("?token={encoded}")), "?token=***");
+    }
+
+    #[test]
+    fn test_short_values_and_clean_text_untouched() {
+        let r = Redactor::new(["abc"]);
+        assert!(r.is_empty());
+        let r = Redactor::new([SECRET]);
+        assert!(matches!(r.redact("nothing to see"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_overlapping_secrets_masked_once() {
+        let r = Redactor::new(["password123", "word123456"]);
+        assert_eq!(r.redact("x password123456 y"), "x *** y");
+    }
+}
@@ -0,0 +1,485 @@
+// ============================================================
+// DevFlow Studio — Retry Policies
+// Re-runs a failed node in the backend, so retries survive a
+// webview reload. Exponential backoff with jitter and a cap,
+// filters on why the attempt failed (exit codes, timeout,
+// OOM, stderr pattern, transient signals) and manual retries
+// approved through `approve_retry`. Every attempt is recorded
+// on the final CommandResult.
+// ============================================================
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::oneshot;
+
+use super::executor::{run_command, CommandResult, ExecRequest};
+use super::log_event::{now_rfc3339, LogEmitter, LogLevel};
+use super::process::{ProcessRegistry, Registration};
+use super::termination::Termination;
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RetryStrategy {
+    #[default]
+    None,
+    /// Wait for `approve_retry` before each retry.
+    Manual,
+    /// Retry immediately.
+    Auto,
+    /// Wait `backoffMs * 2^(n-1)`, capped at `maxDelayMs`.
+    Exponential,
+}
+
+/// Which failures are worth retrying. With nothing set, any failure is.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetryOn {
+    pub exit_codes: Vec<i32>,
+    pub timed_out: bool,
+    pub oom: bool,
+    /// Regex matched against the attempt's stderr.
+    pub stderr_pattern: Option<String>,
+    /// Timeouts, OOM kills and SIGKILL/SIGTERM-style signals.
+    pub transient: bool,
+}
+
+/// Per-node `config.retryPolicy`, as stored by the node settings panel.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetryPolicy {
+    pub strategy: RetryStrategy,
+    /// Retries after the first attempt.
+    pub max_attempts: u32,
+    pub backoff_ms: u64,
+    pub max_delay_ms: u64,
+    /// Randomise each delay between half and all of it, so nodes that
+    /// failed together don't retry in lockstep.
+    pub jitter: bool,
+    pub retry_on: RetryOn,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            strategy: RetryStrategy::None,
+            max_attempts: 2,
+            backoff_ms: 1000,
+            max_delay_ms: 30_000,
+            jitter: true,
+            retry_on: RetryOn::default(),
+        }
+    }
+}
+
+/// One run of a node's command.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Attempt {
+    /// 1-based.
+    pub attempt: u32,
+    pub started_at: String,
+    pub duration_ms: u64,
+    pub exit_code: i32,
+    pub termination: Termination,
+    /// Wait before the next attempt, when one followed.
+    pub retry_delay_ms: Option<u64>,
+}
+
+/// `retry_on` with its stderr pattern compiled.
+struct Filter<'a> {
+    retry_on: &'a RetryOn,
+    stderr: Option<Regex>,
+    /// Exit codes the node treats as success; never retried.
+    allowed_exit_codes: &'a [i32],
+}
+
+impl<'a> Filter<'a> {
+    fn new(retry_on: &'a RetryOn, allowed_exit_codes: &'a [i32]) -> Result<Self, String> {
+        let stderr = retry_on
+            .stderr_pattern
+            .as_deref()
+            .filter(|p| !p.is_empty())
+            .map(|p| Regex::new(p).map_err(|e| format!("Invalid retry stderr pattern: {e}")))
+            .transpose()?;
+        Ok(Self { retry_on, stderr, allowed_exit_codes })
+    }
+
+    fn matches(&self, result: &CommandResult) -> bool {
+        let on = self.retry_on;
+        if result.termination.allows(self.allowed_exit_codes) || result.termination == Termination::Cancelled {
+            return false;
+        }
+        if on.exit_codes.is_empty() && !on.timed_out && !on.oom && !on.transient && self.stderr.is_none() {
+            return true;
+        }
+        matches!(result.termination, Termination::Exited { code } if on.exit_codes.contains(&code))
+            || (on.timed_out && result.termination == Termination::TimedOut)
+            || (on.oom && result.termination == Termination::OomKilled)
+            || (on.transient && result.termination.is_transient())
+            || self.stderr.as_ref().is_some_and(|re| re.is_match(&result.stderr))
+    }
+}
+
+impl RetryPolicy {
+    /// Wait after failed attempt `attempt` (1-based). `random` is in `[0, 1)`.
+    pub fn delay(&self, attempt: u32, random: f64) -> Duration {
+        let ms = match self.strategy {
+            RetryStrategy::Exponential => {
+                let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+                self.backoff_ms.saturating_mul(factor).min(self.max_delay_ms)
+            }
+            _ => 0,
+        };
+        let ms = if self.jitter { (ms as f64 * (0.5 + random / 2.0)) as u64 } else { ms };
+        Duration::from_millis(ms)
+    }
+
+    fn retries(&self) -> u32 {
+        match self.strategy {
+            RetryStrategy::None => 0,
+            _ => self.max_attempts,
+        }
+    }
+}
+
+/// A number in `[0, 1)` from the std hasher's random keys; good enough for jitter.
+fn random_unit() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64));
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// ── Manual approval ───────────────────────────────────────────────────────────
+
+/// Emitted when a node is waiting for `approve_retry`.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryPending {
+    pub run_id: Option<String>,
+    pub node_id: String,
+    /// The attempt that would run next.
+    pub attempt: u32,
+    pub error: String,
+}
+
+/// Nodes waiting for a manual retry decision, by run and node ID.
+#[derive(Default)]
+pub struct RetryGate {
+    waiting: Mutex<HashMap<(String, String), oneshot::Sender<bool>>>,
+}
+
+impl RetryGate {
+    fn wait(&self, run_id: Option<&str>, node_id: &str) -> oneshot::Receiver<bool> {
+        let (tx, rx) = oneshot::channel();
+        let key = (run_id.unwrap_or_default().to_string(), node_id.to_string());
+        self.waiting.lock().unwrap().insert(key, tx);
+        rx
+    }
+
+    fn decide(&self, run_id: &str, node_id: &str, approved: bool) -> bool {
+        let key = (run_id.to_string(), node_id.to_string());
+        match self.waiting.lock().unwrap().remove(&key) {
+            Some(tx) => tx.send(approved).is_ok(),
+            None => false,
+        }
+    }
+}
+
+/// Allow (or with `approved: false`, decline) the pending manual retry of a node.
+#[tauri::command]
+pub fn approve_retry(
+    gate: State<'_, RetryGate>,
+    run_id: String,
+    node_id: String,
+    approved: Option<bool>,
+) -> Result<(), String> {
+    if gate.decide(&run_id, &node_id, approved.unwrap_or(true)) {
+        Ok(())
+    } else {
+        Err(format!("Node '{node_id}' is not waiting for a retry"))
+    }
+}
+
+// ── Retry loop ────────────────────────────────────────────────────────────────
+
+/// Run a node's command, retrying according to `policy`. The node stays
+/// registered across attempts and the waits between them, so
+/// `cancel_execution` and `cancel_run` end the loop at any point; the last
+/// attempt's result is returned then.
+pub async fn run_with_retry(
+    app: &AppHandle,
+    req: ExecRequest,
+    policy: &RetryPolicy,
+    allowed_exit_codes: &[i32],
+    log: LogEmitter,
+) -> Result<CommandResult, String> {
+    let filter = Filter::new(&policy.retry_on, allowed_exit_codes)?;
+    let registration = app.state::<ProcessRegistry>().register(&req.node_id, req.run_id.as_deref());
+    let mut attempts = Vec::new();
+    let mut attempt = 1;
+    let mut last: Option<CommandResult> = None;
+    loop {
+        let started_at = now_rfc3339();
+        let mut result = match run_command(app, req.clone(), log.clone(), &registration).await {
+            Ok(result) => result,
+            Err(e) => return setup_failed(last, attempts, attempt, e, &registration, &log),
+        };
+        attempts.push(Attempt {
+            attempt,
+            started_at,
+            duration_ms: result.duration_ms,
+            exit_code: result.exit_code,
+            termination: result.termination.clone(),
+            retry_delay_ms: None,
+        });
+
+        if attempt > policy.retries() || !filter.matches(&result) {
+            result.attempts = attempts;
+            registration.finish(&result);
+            return Ok(result);
+        }
+
+        let proceed = if policy.strategy == RetryStrategy::Manual {
+            let decision = app.state::<RetryGate>().wait(req.run_id.as_deref(), &req.node_id);
+            log.system(LogLevel::Warn, format!("{} — waiting for approval to retry", result.termination));
+            let _ = app.emit("retry-approval-required", RetryPending {
+                run_id: req.run_id.clone(),
+                node_id: req.node_id.clone(),
+                attempt: attempt + 1,
+                error: result.termination.to_string(),
+            });
+            tokio::select! {
+                approved = decision => approved.unwrap_or(false),
+                _ = cancelled(&registration) => false,
+            }
+        } else {
+            let delay = policy.delay(attempt, random_unit());
+            if let Some(last) = attempts.last_mut() {
+                last.retry_delay_ms = Some(delay.as_millis() as u64);
+            }
+            log.system(
+                LogLevel::Warn,
+                format!("{} — retrying in {:.1}s ({}/{})", result.termination, delay.as_secs_f64(), attempt, policy.retries()),
+            );
+            backoff(delay, &registration).await
+        };
+
+        // A cancel that lands after the wait still stops the next attempt
+        if !proceed || registration.is_cancelled() {
+            // A cancelled wait leaves its gate entry behind
+            app.state::<RetryGate>().decide(req.run_id.as_deref().unwrap_or_default(), &req.node_id, false);
+            log.system(LogLevel::Info, "Retry abandoned".into());
+            result.attempts = attempts;
+            registration.finish(&result);
+            return Ok(result);
+        }
+        attempt += 1;
+        log.system(LogLevel::Info, format!("Retry attempt {attempt}..."));
+        last = Some(result);
+    }
+}
+
+/// Attempt `attempt` could not be started. The first one fails the node with
+/// the error; a retry that can't start ends the loop with the previous
+/// attempt's result and every attempt so far, like any other last attempt.
+fn setup_failed(
+    last: Option<CommandResult>,
+    attempts: Vec<Attempt>,
+    attempt: u32,
+    error: String,
+    registration: &Registration,
+    log: &LogEmitter,
+) -> Result<CommandResult, String> {
+    let Some(mut result) = last else { return Err(error) };
+    log.system(LogLevel::Error, format!("Retry attempt {attempt} could not start: {error}"));
+    result.attempts = attempts;
+    registration.finish(&result);
+    Ok(result)
+}
+
+/// Resolves once the node is cancelled, including by a cancel that came
+/// before the wait started.
+async fn cancelled(registration: &Registration) {
+    while !registration.is_cancelled() {
+        registration.cancel.notified().await;
+    }
+}
+
+/// Sleep before the next attempt; `false` if the node was cancelled meanwhile.
+async fn backoff(delay: Duration, registration: &Registration) -> bool {
+    tokio::select! {
+        _ = tokio::time::sleep(delay) => !registration.is_cancelled(),
+        _ = cancelled(registration) => false,
+    }
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failed(termination: Termination, stderr: &str) -> CommandResult {
+        CommandResult {
+            stdout: String::new(),
+            stderr: stderr.into(),
+            log_file_path: None,
+            truncated: false,
+            exit_code: match termination {
+                Termination::Exited { code } => code,
+                _ => -1,
+            },
+            max_cpu: 0.0,
+            max_memory_mb: 0,
+            max_threads: 0,
+            disk_read_bytes: 0,
+            disk_written_bytes: 0,
+            samples: Vec::new(),
+            duration_ms: 10,
+            timed_out: termination == Termination::TimedOut,
+            cancelled: termination == Termination::Cancelled,
+            kill_signal: None,
+            termination,
+            outputs: Default::default(),
+            attempts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_policy_from_node_config() {
+        let policy: RetryPolicy = serde_json::from_value(serde_json::json!({
+            "strategy": "exponential", "maxAttempts": 3, "retryOn": { "exitCodes": [75], "oom": true }
+        }))
+        .unwrap();
+        assert_eq!(policy.strategy, RetryStrategy::Exponential);
+        assert_eq!(policy.retries(), 3);
+        assert_eq!(policy.backoff_ms, 1000);
+        assert!(policy.jitter);
+
+        let legacy: RetryPolicy = serde_json::from_value(serde_json::json!({ "strategy": "none", "maxAttempts": 2 })).unwrap();
+        assert_eq!(legacy.retries(), 0);
+    }
+
+    #[test]
+    fn test_backoff_is_capped_and_jittered() {
+        let policy = RetryPolicy { strategy: RetryStrategy::Exponential, jitter: false, ..Default::default() };
+        assert_eq!(policy.delay(1, 0.0), Duration::from_millis(1000));
+        assert_eq!(policy.delay(3, 0.0), Duration::from_millis(4000));
+        assert_eq!(policy.delay(10, 0.0), Duration::from_millis(30_000));
+        assert_eq!(policy.delay(200, 0.0), Duration::from_millis(30_000));
+
+        let jittered = RetryPolicy { jitter: true, ..policy };
+        assert_eq!(jittered.delay(2, 0.0), Duration::from_millis(1000));
+        assert!(jittered.delay(2, 0.999) <= Duration::from_millis(2000));
+
+        let auto = RetryPolicy { strategy: RetryStrategy::Auto, ..Default::default() };
+        assert_eq!(auto.delay(1, 0.5), Duration::ZERO);
+
+        let r = random_unit();
+        assert!((0.0..1.0).contains(&r));
+    }
+
+    #[test]
+    fn test_retry_filters() {
+        let any = RetryOn::default();
+        let filter = Filter::new(&any, &[]).unwrap();
+        assert!(filter.matches(&failed(Termination::Exited { code: 1 }, "")));
+        assert!(!filter.matches(&failed(Termination::Exited { code: 0 }, "")));
+        assert!(!filter.matches(&failed(Termination::Cancelled, "")));
+
+        let on = RetryOn {
+            exit_codes: vec![75],
+            timed_out: true,
+            stderr_pattern: Some("connection (reset|refused)".into()),
+            ..Default::default()
+        };
+        let filter = Filter::new(&on, &[]).unwrap();
+        assert!(filter.matches(&failed(Termination::Exited { code: 75 }, "")));
+        assert!(filter.matches(&failed(Termination::TimedOut, "")));
+        assert!(filter.matches(&failed(Termination::Exited { code: 1 }, "error: connection reset by peer")));
+        assert!(!filter.matches(&failed(Termination::Exited { code: 1 }, "syntax error")));
+        assert!(!filter.matches(&failed(Termination::OomKilled, "")));
+
+        let transient = RetryOn { transient: true, ..Default::default() };
+        let filter = Filter::new(&transient, &[]).unwrap();
+        assert!(filter.matches(&failed(Termination::OomKilled, "")));
+        assert!(!filter.matches(&failed(Termination::Signaled { signal: "SIGSEGV".into(), core_dumped: true }, "")));
+
+        let bad = RetryOn { stderr_pattern: Some("(".into()), ..Default::default() };
+        assert!(Filter::new(&bad, &[]).is_err());
+
+        let allowed = Filter::new(&any, &[1]).unwrap();
+        assert!(!allowed.matches(&failed(Termination::Exited { code: 1 }, "")));
+        assert!(allowed.matches(&failed(Termination::Exited { code: 2 }, "")));
+    }
+
+    #[test]
+    fn test_retry_gate() {
+        let gate = RetryGate::default();
+        let mut rx = gate.wait(Some("run-1"), "build");
+        assert!(!gate.decide("run-1", "test", true));
+        assert!(gate.decide("run-1", "build", false));
+        assert_eq!(rx.try_recv(), Ok(false));
+        assert!(!gate.decide("run-1", "build", true));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_during_backoff_stops_the_retry() {
+        let registry = ProcessRegistry::default();
+        let reg = registry.register("build", Some("run-1"));
+        let started = std::time::Instant::now();
+        let (proceed, _) = tokio::join!(backoff(Duration::from_secs(30), &reg), async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            registry.cancel(None, Some("run-1"));
+        });
+        assert!(!proceed);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // A cancel between attempts, with nobody waiting, still ends the next wait
+        let reg = registry.register("test", Some("run-2"));
+        registry.cancel(Some("test"), None);
+        assert!(!backoff(Duration::from_secs(30), &reg).await);
+        assert!(backoff(Duration::from_millis(1), &registry.register("lint", None)).await);
+    }
+
+    #[tokio::test]
+    async fn test_retry_that_cannot_start_keeps_the_attempts() {
+        let registry = ProcessRegistry::default();
+        let lines = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let log = LogEmitter::with_sink({
+            let lines = lines.clone();
+            move |event| lines.lock().unwrap().push(event.text)
+        }, "build", Some("run-1"));
+
+        // Nothing ran yet: the node fails with the setup error
+        let err = setup_failed(None, Vec::new(), 1, "Failed to spawn sh".into(), &registry.register("lint", None), &log);
+        assert_eq!(err.unwrap_err(), "Failed to spawn sh");
+
+        let reg = registry.register("build", Some("run-1"));
+        let first = failed(Termination::Exited { code: 1 }, "");
+        let attempts = vec![Attempt {
+            attempt: 1,
+            started_at: now_rfc3339(),
+            duration_ms: first.duration_ms,
+            exit_code: 1,
+            termination: first.termination.clone(),
+            retry_delay_ms: Some(0),
+        }];
+        let result = setup_failed(Some(first), attempts, 2, "Failed to spawn sh".into(), &reg, &log).unwrap();
+        assert_eq!(result.attempts.len(), 1);
+        assert_eq!(result.termination, Termination::Exited { code: 1 });
+        assert!(lines.lock().unwrap().iter().any(|l| l.contains("Retry attempt 2 could not start")));
+
+        // The registration was finished, so a cancel waiting on it gets the result
+        let pending = registry.cancel(Some("build"), None);
+        assert_eq!(pending.len(), 1);
+        let published = crate::commands::process::wait_finished(pending.into_iter().next().unwrap()).await;
+        assert_eq!(published.map(|r| r.attempts.len()), Some(1));
+    }
+}
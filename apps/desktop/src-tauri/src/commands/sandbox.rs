@@ -0,0 +1,180 @@
+// ============================================================
+// DevFlow Studio — Sandbox Execution Profile (Linux)
+// Runs untrusted node commands under bubblewrap: fresh user,
+// PID and mount namespaces, the host filesystem read-only,
+// $HOME replaced by an empty tmpfs and only the project (plus
+// any configured paths) writable. Network is off by default.
+// /run is emptied too: a read-only bind doesn't stop connect()
+// on the sockets there (ssh-agent, the session bus and with it
+// the Secret Service, docker.sock). The environment is cleared
+// down to an allowlist plus the node's own env vars.
+// ============================================================
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::executor::Program;
+use super::shell::find_on_path;
+
+/// Host env vars a sandboxed command still sees. Everything else, such as
+/// SSH_AUTH_SOCK, DBUS_SESSION_BUS_ADDRESS or tokens, is dropped.
+pub const ENV_ALLOWLIST: &[&str] = &["PATH", "HOME", "LANG", "LC_ALL", "LC_CTYPE", "TERM", "TZ"];
+
+/// Directories replaced by an empty tmpfs, hiding the host sockets in them.
+const SOCKET_DIRS: &[&str] = &["/run", "/var/run"];
+
+/// Configuration for the sandbox execution profile.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct SandboxConfig {
+    /// Keep the host network namespace. Off unless asked for.
+    #[serde(default)]
+    pub allow_network: bool,
+    /// Extra host paths the command may write to, besides the project.
+    #[serde(default)]
+    pub read_write: Vec<String>,
+}
+
+/// Wraps a program in a `bwrap` invocation. The project directory is the
+/// only writable location by default and becomes the working directory.
+pub fn wrap_for_sandbox(program: &Program, cwd: &Option<String>, cfg: &SandboxConfig) -> Result<Vec<String>, String> {
+    if !cfg!(target_os = "linux") {
+        return Err("The sandbox profile is only available on Linux".into());
+    }
+    let bwrap = find_on_path("bwrap")
+        .ok_or("The sandbox profile needs bubblewrap: install the `bubblewrap` package")?;
+    let project = absolute(cwd.as_deref().ok_or("The sandbox profile requires a project directory")?)?;
+
+    let mut argv = vec![
+        bwrap.display().to_string(),
+        "--die-with-parent".into(),
+        // Detach from the controlling terminal (blocks TIOCSTI injection)
+        "--new-session".into(),
+        "--unshare-all".into(),
+    ];
+    if cfg.allow_network {
+        argv.push("--share-net".into());
+    }
+    argv.extend(["--ro-bind", "/", "/", "--dev", "/dev", "--proc", "/proc", "--tmpfs", "/tmp"].map(String::from));
+    // /var/run is usually a symlink to /run, which is already covered
+    for dir in SOCKET_DIRS.iter().filter(|d| Path::new(d).symlink_metadata().is_ok_and(|m| m.is_dir())) {
+        argv.extend(["--tmpfs".into(), dir.to_string()]);
+    }
+    // Mounted before the project binds so a project inside $HOME stays visible
+    if let Some(home) = std::env::var_os("HOME").filter(|h| !h.is_empty()) {
+        argv.extend(["--tmpfs".into(), PathBuf::from(home).display().to_string()]);
+    }
+    for path in cfg.read_write.iter().map(String::as_str).chain([project.to_str().unwrap_or_default()]) {
+        let path = absolute(path)?.display().to_string();
+        argv.extend(["--bind".into(), path.clone(), path]);
+    }
+    argv.extend(["--chdir".into(), project.display().to_string(), "--".into()]);
+
+    match program {
+        Program::Shell(command) => argv.extend(["/bin/sh".into(), "-c".into(), command.clone()]),
+        Program::Argv(inner) => argv.extend(inner.iter().cloned()),
+    }
+    Ok(argv)
+}
+
+/// The host part of a sandboxed command's environment. The caller clears the
+/// bwrap process's env and sets only these plus the node's env vars; bwrap's
+/// own `--clearenv`/`--setenv` would put secret values on its command line.
+pub fn sandbox_env(host: impl IntoIterator<Item = (String, String)>) -> Vec<(String, String)> {
+    host.into_iter().filter(|(name, _)| ENV_ALLOWLIST.contains(&name.as_str())).collect()
+}
+
+/// Canonical form of an existing path: bwrap needs absolute paths, and
+/// resolving symlinks here keeps a bind from escaping where it points.
+fn absolute(path: &str) -> Result<PathBuf, String> {
+    Path::new(path)
+        .canonicalize()
+        .map_err(|e| format!("Sandbox path '{path}' is not accessible: {e}"))
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    fn opts_before_separator(argv: &[String]) -> &[String] {
+        &argv[..argv.iter().position(|a| a == "--").unwrap()]
+    }
+
+    #[test]
+    fn test_sandbox_layout() {
+        if find_on_path("bwrap").is_none() {
+            return;
+        }
+        let project = std::env::temp_dir().canonicalize().unwrap().display().to_string();
+        let argv = wrap_for_sandbox(&Program::Shell("make".into()), &Some(project.clone()), &SandboxConfig::default())
+            .unwrap();
+        let opts = opts_before_separator(&argv);
+        assert!(opts.contains(&"--unshare-all".to_string()));
+        assert!(!opts.contains(&"--share-net".to_string()));
+        let bind = opts.windows(3).position(|w| w[0] == "--bind" && w[1] == project && w[2] == project);
+        let ro_root = opts.windows(3).position(|w| w[0] == "--ro-bind" && w[1] == "/").unwrap();
+        assert!(bind.unwrap() > ro_root);
+        assert!(opts.windows(2).any(|w| w[0] == "--tmpfs" && w[1] == "/run"));
+        assert_eq!(argv[argv.len() - 3..], ["/bin/sh", "-c", "make"]);
+
+        let cfg = SandboxConfig { allow_network: true, ..Default::default() };
+        let argv = wrap_for_sandbox(&Program::Argv(vec!["true".into()]), &Some(project), &cfg).unwrap();
+        assert!(opts_before_separator(&argv).contains(&"--share-net".to_string()));
+    }
+
+    #[test]
+    fn test_sandbox_env_drops_agent_and_bus_sockets() {
+        let host = [
+            ("PATH", "/usr/bin"),
+            ("LANG", "C.UTF-8"),
+            ("SSH_AUTH_SOCK", "/run/user/1000/ssh-agent.sock"),
+            ("DBUS_SESSION_BUS_ADDRESS", "unix:path=/run/user/1000/bus"),
+            ("XDG_RUNTIME_DIR", "/run/user/1000"),
+            ("GITHUB_TOKEN", "ghp_x"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        let env = sandbox_env(host);
+        let names: Vec<&str> = env.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(names, ["PATH", "LANG"]);
+    }
+
+    /// End to end: inside the sandbox, host sockets and their env vars are gone.
+    #[test]
+    fn test_sandbox_hides_host_sockets() {
+        if find_on_path("bwrap").is_none() {
+            return;
+        }
+        let project = std::env::temp_dir().canonicalize().unwrap().display().to_string();
+        let script = "for v in SSH_AUTH_SOCK DBUS_SESSION_BUS_ADDRESS; do env | grep -q \"^$v=\" && echo \"env $v\"; done; \
+                      for p in /run/user /run/dbus /run/docker.sock /var/run/docker.sock; do [ -e $p ] && echo \"path $p\"; done; \
+                      echo done";
+        let argv = wrap_for_sandbox(&Program::Argv(vec!["/bin/sh".into(), "-c".into(), script.into()]), &Some(project), &SandboxConfig::default())
+            .unwrap();
+        let host = [
+            ("PATH".to_string(), std::env::var("PATH").unwrap_or_default()),
+            ("SSH_AUTH_SOCK".to_string(), "/run/user/1000/ssh-agent.sock".to_string()),
+            ("DBUS_SESSION_BUS_ADDRESS".to_string(), "unix:path=/run/user/1000/bus".to_string()),
+        ];
+        let output = std::process::Command::new(&argv[0])
+            .args(&argv[1..])
+            .env_clear()
+            .envs(sandbox_env(host))
+            .output()
+            .unwrap();
+        // No user namespaces available (e.g. in a container): nothing to check
+        if !output.status.success() {
+            return;
+        }
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "done");
+    }
+
+    #[test]
+    fn test_sandbox_requires_existing_project() {
+        let cfg = SandboxConfig::default();
+        assert!(wrap_for_sandbox(&Program::Shell("true".into()), &None, &cfg).is_err());
+        let missing = Some("/nonexistent/devflow-project".to_string());
+        assert!(wrap_for_sandbox(&Program::Shell("true".into()), &missing, &cfg).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+// ============================================================
+// DevFlow Studio — Temporary Secret Files
+// Some tools only take credentials from a file (docker's
+// --env-file, ssh's -i). These files are created owner-only
+// (0600) in the temp dir and removed as soon as they're dropped.
+// ============================================================
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A private temporary file, deleted on drop.
+#[derive(Debug)]
+pub struct SecretFile {
+    path: PathBuf,
+}
+
+impl SecretFile {
+    /// Create a new file named `devflow-<prefix>-<pid>-<n>` readable only by the current user.
+    pub fn create(prefix: &str, contents: &[u8]) -> std::io::Result<Self> {
+        let name = format!(
+            "devflow-{}-{}-{}",
+            prefix,
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = std::env::temp_dir().join(name);
+
+        let mut file = open_private(&path)?;
+        // Owned from here on, so a failed write still removes the file
+        let secret_file = Self { path };
+        file.write_all(contents)?;
+        file.sync_all()?;
+        Ok(secret_file)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(unix)]
+fn open_private(path: &Path) -> std::io::Result<File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    // `create_new` refuses to follow a pre-planted file or symlink
+    OpenOptions::new().write(true).create_new(true).mode(0o600).open(path)
+}
+
+#[cfg(not(unix))]
+fn open_private(path: &Path) -> std::io::Result<File> {
+    // The per-user temp dir on Windows is already private to the account
+    OpenOptions::new().write(true).create_new(true).open(path)
+}
+
+impl Drop for SecretFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_file_is_private_and_removed_on_drop() {
+        let file = SecretFile::create("test", b"TOKEN=abc\n").unwrap();
+        let path = file.path().to_path_buf();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "TOKEN=abc\n");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        drop(file);
+        assert!(!path.exists());
+    }
+}
@@ -16,13 +16,28 @@ use serde::{Deserialize, Serialize};
 const SERVICE_NAME: &str = "devflow-studio";
 
 /// Wrapper so the secret value is wiped from heap when dropped.
-type SecretValue = Zeroizing<String>;
+pub type SecretValue = Zeroizing<String>;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SecretEntry {
     pub key: String,
 }
 
+// ── Backend access ─────────────────────────────────────────────────────────────
+
+/// Read a secret for use inside the backend (e.g. injecting it into a child's
+/// environment). The value never crosses IPC and is wiped when dropped.
+pub fn read_secret(key: &str) -> Result<Option<SecretValue>, String> {
+    let entry = Entry::new(SERVICE_NAME, key)
+        .map_err(|e| format!("Failed to open keyring for '{}': {}", key, e))?;
+
+    match entry.get_password() {
+        Ok(password) => Ok(Some(Zeroizing::new(password))),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to retrieve secret '{}': {}", key, e)),
+    }
+}
+
 // ── Tauri commands ─────────────────────────────────────────────────────────────
 
 /// Store a secret in the OS credential store.
@@ -47,14 +62,7 @@ pub fn store_secret(key: String, value: String) -> Result<(), String> {
 /// The caller must handle the returned value securely.
 #[tauri::command]
 pub fn get_secret(key: String) -> Result<Option<String>, String> {
-    let entry = Entry::new(SERVICE_NAME, &key)
-        .map_err(|e| format!("Failed to open keyring for '{}': {}", key, e))?;
-
-    match entry.get_password() {
-        Ok(password) => Ok(Some(password)),
-        Err(keyring::Error::NoEntry) => Ok(None),
-        Err(e) => Err(format!("Failed to retrieve secret '{}': {}", key, e)),
-    }
+    Ok(read_secret(&key)?.map(|value| value.to_string()))
 }
 
 /// Delete a secret from the OS credential store.
@@ -0,0 +1,215 @@
+// ============================================================
+// DevFlow Studio — Script Interpreter Selection
+// Resolves the interpreter a `scriptRun` node's script runs
+// under: `auto`, a named shell looked up on PATH, an absolute
+// path, or a full argv such as `bash -euo pipefail -c`. The
+// interpreter is checked before spawning so a missing shell
+// fails with a clear message instead of a bare ENOENT.
+// ============================================================
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// Shells that accept POSIX `sh -c` scripts; `auto` only uses `$SHELL` if it's one of these.
+const POSIX_SHELLS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh", "mksh", "ash"];
+
+/// How to run a shell script.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(try_from = "ShellSpecRepr")]
+pub enum ShellSpec {
+    /// `$SHELL` when POSIX-compatible, else `/bin/sh` (PowerShell on Windows).
+    #[default]
+    Auto,
+    /// A shell looked up on `PATH`, e.g. `bash`, `zsh`, `pwsh`.
+    Named(String),
+    /// An absolute path to the interpreter.
+    Path(PathBuf),
+    /// Interpreter plus arguments; the script is appended as the last argument.
+    Custom(Vec<String>),
+}
+
+/// Accepted as a string (`"bash"`, `"/bin/zsh"`, `"bash -euo pipefail -c"`)
+/// or, for paths containing spaces, an argv array.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ShellSpecRepr {
+    Text(String),
+    Argv(Vec<String>),
+}
+
+impl TryFrom<ShellSpecRepr> for ShellSpec {
+    type Error = String;
+
+    fn try_from(repr: ShellSpecRepr) -> Result<Self, String> {
+        match repr {
+            ShellSpecRepr::Text(text) => Self::parse(&text),
+            ShellSpecRepr::Argv(argv) if argv.is_empty() => Err("Shell argv must not be empty".into()),
+            ShellSpecRepr::Argv(argv) => Ok(ShellSpec::Custom(argv)),
+        }
+    }
+}
+
+impl ShellSpec {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.is_empty() || text.eq_ignore_ascii_case("auto") {
+            return Ok(ShellSpec::Auto);
+        }
+        if text.contains(char::is_whitespace) {
+            return Ok(ShellSpec::Custom(text.split_whitespace().map(str::to_string).collect()));
+        }
+        if Path::new(text).is_absolute() {
+            return Ok(ShellSpec::Path(PathBuf::from(text)));
+        }
+        if text.contains(['/', '\\']) {
+            return Err(format!("Shell '{text}' must be a name on PATH or an absolute path"));
+        }
+        Ok(ShellSpec::Named(text.to_string()))
+    }
+
+    /// The full argv that runs `script`, with the interpreter resolved and
+    /// checked to exist.
+    pub fn argv(&self, script: &str) -> Result<Vec<String>, String> {
+        let mut argv = match self {
+            ShellSpec::Auto => {
+                let (shell, flag) = detect_shell();
+                vec![shell, flag]
+            }
+            ShellSpec::Named(name) => interpreter(&resolve(name)?),
+            ShellSpec::Path(path) => interpreter(&resolve(&path.to_string_lossy())?),
+            ShellSpec::Custom(custom) => {
+                let mut argv = custom.clone();
+                argv[0] = resolve(&custom[0])?.display().to_string();
+                argv
+            }
+        };
+        argv.push(script.to_string());
+        Ok(argv)
+    }
+}
+
+/// Resolve a shell name or absolute path to an existing executable.
+fn resolve(shell: &str) -> Result<PathBuf, String> {
+    if Path::new(shell).is_absolute() {
+        let path = PathBuf::from(shell);
+        return if is_executable(&path) {
+            Ok(path)
+        } else {
+            Err(format!("Shell '{shell}' does not exist or is not executable"))
+        };
+    }
+    find_on_path(shell).ok_or_else(|| format!("Shell '{shell}' was not found on PATH"))
+}
+
+/// `shell` plus the flags that make it run its next argument as a script.
+fn interpreter(shell: &Path) -> Vec<String> {
+    std::iter::once(shell.display().to_string())
+        .chain(command_flags(shell).iter().map(|flag| flag.to_string()))
+        .collect()
+}
+
+/// Flags that make `shell` run its next argument as a script.
+fn command_flags(shell: &Path) -> &'static [&'static str] {
+    let name = shell.file_stem().map(|s| s.to_string_lossy().to_ascii_lowercase()).unwrap_or_default();
+    match name.as_str() {
+        "powershell" | "pwsh" => &["-NoProfile", "-Command"],
+        "cmd" => &["/C"],
+        _ => &["-c"],
+    }
+}
+
+/// Determine the host shell to use.
+pub fn detect_shell() -> (String, String) {
+    if cfg!(target_os = "windows") {
+        ("powershell.exe".to_string(), "-Command".to_string())
+    } else {
+        // Login shells like fish or nushell don't speak POSIX sh
+        let env_shell = std::env::var("SHELL")
+            .ok()
+            .filter(|shell| {
+                let name = Path::new(shell).file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                POSIX_SHELLS.contains(&name)
+            })
+            .unwrap_or_else(|| "/bin/sh".to_string());
+        (env_shell, "-c".to_string())
+    }
+}
+
+/// First executable named `name` on `PATH`.
+pub fn find_on_path(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .flat_map(|dir| {
+            let candidate = dir.join(name);
+            // Windows executables are found without their extension too
+            let exe = (cfg!(windows) && candidate.extension().is_none()).then(|| candidate.with_extension("exe"));
+            std::iter::once(candidate).chain(exe)
+        })
+        .find(|candidate| is_executable(candidate))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata().is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_shell_not_empty() {
+        let (shell, flag) = detect_shell();
+        assert!(!shell.is_empty());
+        assert!(!flag.is_empty());
+        assert!(!shell.ends_with("fish"));
+    }
+
+    #[test]
+    fn test_shell_spec_parse() {
+        assert_eq!(ShellSpec::parse("auto").unwrap(), ShellSpec::Auto);
+        assert_eq!(ShellSpec::parse("").unwrap(), ShellSpec::Auto);
+        assert_eq!(ShellSpec::parse("zsh").unwrap(), ShellSpec::Named("zsh".into()));
+        assert_eq!(ShellSpec::parse("/bin/bash").unwrap(), ShellSpec::Path("/bin/bash".into()));
+        assert_eq!(
+            ShellSpec::parse("bash -euo pipefail -c").unwrap(),
+            ShellSpec::Custom(vec!["bash".into(), "-euo".into(), "pipefail".into(), "-c".into()])
+        );
+        assert!(ShellSpec::parse("bin/bash").is_err());
+
+        let spec: ShellSpec = serde_json::from_value(serde_json::json!(["/opt/my shell/bin/sh", "-c"])).unwrap();
+        assert_eq!(spec, ShellSpec::Custom(vec!["/opt/my shell/bin/sh".into(), "-c".into()]));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_shell_spec_argv() {
+        let argv = ShellSpec::parse("sh").unwrap().argv("echo hi").unwrap();
+        assert!(argv[0].ends_with("/sh"));
+        assert_eq!(argv[1..], ["-c", "echo hi"]);
+
+        let argv = ShellSpec::parse("/bin/sh -e -c").unwrap().argv("true").unwrap();
+        assert_eq!(argv, ["/bin/sh", "-e", "-c", "true"]);
+
+        let err = ShellSpec::parse("no-such-shell-xyz").unwrap().argv("true").unwrap_err();
+        assert!(err.contains("not found on PATH"), "{err}");
+        let err = ShellSpec::parse("/nonexistent/sh").unwrap().argv("true").unwrap_err();
+        assert!(err.contains("does not exist"), "{err}");
+    }
+
+    #[test]
+    fn test_command_flags() {
+        assert_eq!(command_flags(Path::new("/usr/bin/pwsh")), ["-NoProfile", "-Command"]);
+        assert_eq!(command_flags(Path::new("cmd.exe")), ["/C"]);
+        assert_eq!(command_flags(Path::new("/bin/zsh")), ["-c"]);
+    }
+}
@@ -0,0 +1,637 @@
+// ============================================================
+// DevFlow Studio — SSH Execution Profile
+// Runs node commands on a remote host with host keys verified
+// against a DevFlow-managed known_hosts file (never the user's
+// ~/.ssh/known_hosts, never StrictHostKeyChecking=no). Unknown
+// hosts are trusted explicitly via scan → confirm → trust.
+// A jump host is trusted first; its target is then scanned
+// through it, since it's often unreachable from here.
+// Nodes of one run share a ControlMaster connection per
+// host/user/port, closed when the run finishes.
+// ============================================================
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+use zeroize::Zeroizing;
+
+use super::executor::{env_allowed, shell_escape, Program};
+use super::secret_file::SecretFile;
+use super::secure_storage::{read_secret, SecretValue};
+
+/// Seconds to wait for the TCP connection and handshake by default.
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 15;
+
+/// Idle lifetime of a shared master connection. Runs close theirs explicitly;
+/// this only bounds a master left behind if that never happens (e.g. a crash).
+const CONTROL_PERSIST_SECS: u64 = 600;
+
+/// Configuration for the SSH execution profile.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct SshConfig {
+    pub host: Option<String>,
+    /// Remote user; when unset, ssh's own default (local user / ssh_config) applies.
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    /// Private key file on disk.
+    pub identity_file: Option<String>,
+    /// Name of a private key stored via `store_secret`; written to a
+    /// temporary 0600 file for the duration of the run.
+    pub identity_secret: Option<String>,
+    /// Bastion as `[user@]host[:port]`; verified against the same known_hosts.
+    pub jump_host: Option<String>,
+    /// Directory to `cd` into on the remote side before running the command.
+    pub remote_cwd: Option<String>,
+    /// Node env vars forwarded to the remote command (names or `PREFIX_*`).
+    /// When unset, everything except `SSH_*` (which configures the client) is forwarded.
+    pub env_allowlist: Option<Vec<String>>,
+    pub connect_timeout_secs: Option<u64>,
+}
+
+/// Files an SSH invocation needs on the local side.
+pub struct SshFiles<'a> {
+    pub known_hosts: &'a Path,
+    pub identity: Option<&'a Path>,
+    /// Directory for the run's ControlMaster sockets; `None` opens a fresh connection.
+    pub control_dir: Option<&'a Path>,
+}
+
+/// Host argv plus, when env is forwarded, the script to feed to the remote `sh -s`.
+pub struct SshInvocation {
+    pub argv: Vec<String>,
+    pub stdin_script: Option<SecretValue>,
+}
+
+/// Location of the DevFlow-managed known_hosts file.
+pub fn known_hosts_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {e}"))?
+        .join("ssh");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    Ok(dir.join("known_hosts"))
+}
+
+/// Write the keychain-stored identity for `cfg`, if any, to a private temp file.
+pub fn identity_from_secret(cfg: &SshConfig) -> Result<Option<SecretFile>, String> {
+    let Some(ref key) = cfg.identity_secret else { return Ok(None) };
+    let mut pem = read_secret(key)?.ok_or_else(|| format!("SSH key secret '{key}' not found in OS keychain"))?;
+    // OpenSSH rejects private keys without a trailing newline
+    if !pem.ends_with('\n') {
+        pem.push('\n');
+    }
+    SecretFile::create("ssh-key", pem.as_bytes())
+        .map(Some)
+        .map_err(|e| format!("Failed to write SSH identity file: {e}"))
+}
+
+/// Whether a node env var should be forwarded to the remote command.
+pub fn forwards_to_remote(cfg: &SshConfig, name: &str) -> bool {
+    env_allowed(cfg.env_allowlist.as_deref(), name, "SSH_")
+}
+
+/// Reject values ssh would parse as an option or that can't be a host spec.
+fn host_arg<'a>(field: &str, value: &'a str) -> Result<&'a str, String> {
+    if value.is_empty() || value.starts_with('-') || value.contains(char::is_whitespace) {
+        return Err(format!("Invalid SSH {field} '{value}'"));
+    }
+    Ok(value)
+}
+
+/// Client options shared by the target and jump-host connections.
+fn verification_options(files: &SshFiles, connect_timeout: u64) -> Vec<String> {
+    [
+        // Quoted: ssh splits option values at spaces ("Application Support" on macOS)
+        format!("UserKnownHostsFile=\"{}\"", files.known_hosts.display()),
+        "GlobalKnownHostsFile=/dev/null".to_string(),
+        "StrictHostKeyChecking=yes".to_string(),
+        // Never block on a password or passphrase prompt
+        "BatchMode=yes".to_string(),
+        format!("ConnectTimeout={connect_timeout}"),
+    ]
+    .into_iter()
+    .flat_map(|opt| ["-o".to_string(), opt])
+    .collect()
+}
+
+/// Split a jump host spec `[user@]host[:port]` into destination and port.
+fn split_jump(jump: &str) -> (&str, Option<u16>) {
+    match jump.rsplit_once(':') {
+        Some((dest, port)) => match port.parse::<u16>() {
+            Ok(port) => (dest, Some(port)),
+            Err(_) => (jump, None),
+        },
+        None => (jump, None),
+    }
+}
+
+/// `ProxyCommand=…` reaching the target through `jump`, with the bastion's key
+/// verified like the target's. (-J would not pass our -o options on to it.)
+fn proxy_command(jump: &str, files: &SshFiles, connect_timeout: u64, identity: Option<&Path>) -> Result<String, String> {
+    let mut proxy = vec!["ssh".to_string()];
+    proxy.extend(verification_options(files, connect_timeout));
+    if let Some(identity) = identity {
+        proxy.extend(["-i".into(), identity.display().to_string()]);
+    }
+    let (jump_dest, jump_port) = split_jump(jump);
+    if let Some(port) = jump_port {
+        proxy.extend(["-p".into(), port.to_string()]);
+    }
+    proxy.extend(["-W".into(), "%h:%p".into(), host_arg("jump host", jump_dest)?.to_string()]);
+    Ok(format!("ProxyCommand={}", proxy.iter().map(|a| shell_escape(a)).collect::<Vec<_>>().join(" ")))
+}
+
+/// Builds the `ssh` invocation for a node. `forward` holds the env vars to
+/// export remotely; they go over stdin rather than the visible argv.
+pub fn wrap_for_ssh(
+    program: &Program,
+    cfg: &SshConfig,
+    files: &SshFiles,
+    forward: &[(String, SecretValue)],
+) -> Result<SshInvocation, String> {
+    let host = host_arg("host", cfg.host.as_deref().ok_or("SSH profile requires a host")?)?;
+    let destination = match cfg.user.as_deref() {
+        Some(user) => format!("{}@{}", host_arg("user", user)?, host),
+        None => host.to_string(),
+    };
+    let connect_timeout = cfg.connect_timeout_secs.unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS);
+
+    let mut argv = vec!["ssh".to_string()];
+    argv.extend(verification_options(files, connect_timeout));
+    if let Some(dir) = files.control_dir {
+        // %C hashes local host, remote host, port and user: one master per destination
+        for opt in [
+            "ControlMaster=auto".to_string(),
+            format!("ControlPath={}/%C", dir.display()),
+            format!("ControlPersist={CONTROL_PERSIST_SECS}"),
+        ] {
+            argv.extend(["-o".into(), opt]);
+        }
+    }
+    if let Some(port) = cfg.port {
+        argv.extend(["-p".into(), port.to_string()]);
+    }
+    if let Some(identity) = cfg.identity_file.as_deref().map(Path::new).or(files.identity) {
+        argv.extend(["-i".into(), identity.display().to_string()]);
+        argv.extend(["-o".into(), "IdentitiesOnly=yes".into()]);
+    }
+    if let Some(jump) = cfg.jump_host.as_deref() {
+        let identity = cfg.identity_file.as_deref().map(Path::new).or(files.identity);
+        argv.extend(["-o".into(), proxy_command(jump, files, connect_timeout, identity)?]);
+    }
+    argv.push("--".into());
+    argv.push(destination);
+
+    let mut remote = program.to_shell_string();
+    if let Some(dir) = cfg.remote_cwd.as_deref() {
+        remote = format!("cd {} && {}", shell_escape(dir), remote);
+    }
+
+    // Always run under the remote `sh`, with or without forwarded env, so a
+    // script doesn't change interpreter (the login shell) when env is added
+    if forward.is_empty() {
+        argv.push(format!("sh -c {}", shell_escape(&remote)));
+        return Ok(SshInvocation { argv, stdin_script: None });
+    }
+
+    let mut script = Zeroizing::new(String::new());
+    for (name, value) in forward {
+        let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(format!("Cannot forward ${name} over SSH: not a valid shell variable name"));
+        }
+        script.push_str(&format!("export {}={}\n", name, shell_escape(value)));
+    }
+    script.push_str(&remote);
+    script.push('\n');
+    argv.push("sh -s".into());
+    Ok(SshInvocation { argv, stdin_script: Some(script) })
+}
+
+// ── Connection multiplexing ───────────────────────────────────────────────────
+
+/// Per-run ControlMaster socket directories, managed as Tauri state.
+#[derive(Default)]
+pub struct SshMuxPool {
+    dirs: Mutex<HashMap<String, PathBuf>>,
+}
+
+impl SshMuxPool {
+    /// Socket directory for `run_id`, created (0700) on first use. `None` when
+    /// multiplexing is unavailable, in which case each node connects on its own.
+    pub fn control_dir(&self, run_id: &str) -> Option<PathBuf> {
+        let mut dirs = self.dirs.lock().unwrap();
+        if let Some(dir) = dirs.get(run_id) {
+            return Some(dir.clone());
+        }
+        let dir = create_control_dir()?;
+        dirs.insert(run_id.to_string(), dir.clone());
+        Some(dir)
+    }
+
+    /// Stop every master connection opened for `run_id` and remove its sockets.
+    pub async fn close_run(&self, run_id: &str) -> usize {
+        let Some(dir) = self.dirs.lock().unwrap().remove(run_id) else { return 0 };
+        let sockets: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .map(|entries| entries.flatten().map(|e| e.path()).collect())
+            .unwrap_or_default();
+
+        let mut closed = 0;
+        for socket in sockets {
+            // With an explicit -S the destination is only a placeholder
+            let exited = tokio::process::Command::new("ssh")
+                .arg("-S")
+                .arg(&socket)
+                .args(["-O", "exit", "devflow-mux"])
+                .output()
+                .await
+                .is_ok_and(|out| out.status.success());
+            if exited {
+                closed += 1;
+            }
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+        closed
+    }
+}
+
+/// A fresh private directory under /tmp. Unix socket paths are limited to
+/// ~104 bytes, so the (often long) per-user temp dir is avoided.
+#[cfg(unix)]
+fn create_control_dir() -> Option<PathBuf> {
+    use std::os::unix::fs::DirBuilderExt;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+    let dir = PathBuf::from(format!(
+        "/tmp/devflow-ssh-{}-{}",
+        std::process::id(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+    // Fails if the path already exists, so a pre-planted directory is never used
+    std::fs::DirBuilder::new().mode(0o700).create(&dir).ok()?;
+    Some(dir)
+}
+
+/// OpenSSH for Windows does not support ControlMaster.
+#[cfg(not(unix))]
+fn create_control_dir() -> Option<PathBuf> {
+    None
+}
+
+// ── Host key trust (TOFU) ─────────────────────────────────────────────────────
+
+/// A host key offered by a server, for the user to verify before trusting it.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostKey {
+    /// Host as written to known_hosts (`host` or `[host]:port`).
+    pub host: String,
+    pub key_type: String,
+    /// `SHA256:…`, the format `ssh-keygen -lf` prints.
+    pub fingerprint: String,
+    /// Whether this exact key is already in the managed known_hosts.
+    pub trusted: bool,
+    #[serde(skip)]
+    key: String,
+}
+
+fn known_hosts_pattern(host: &str, port: Option<u16>) -> String {
+    match port {
+        Some(port) if port != 22 => format!("[{host}]:{port}"),
+        _ => host.to_string(),
+    }
+}
+
+/// `SHA256:<base64>` fingerprint of a base64-encoded public key blob.
+fn fingerprint(key_b64: &str) -> Option<String> {
+    let blob = STANDARD.decode(key_b64).ok()?;
+    Some(format!("SHA256:{}", STANDARD_NO_PAD.encode(Sha256::digest(blob))))
+}
+
+/// Parse `ssh-keyscan` output, marking keys already present in `known_hosts`.
+fn parse_keyscan(output: &str, pattern: &str, known_hosts: &str) -> Vec<HostKey> {
+    output
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let (_, key_type, key) = (parts.next()?, parts.next()?, parts.next()?);
+            let trusted = known_hosts.lines().any(|known| {
+                let mut k = known.split_whitespace();
+                k.next().is_some_and(|hosts| hosts.split(',').any(|h| h == pattern))
+                    && k.next() == Some(key_type)
+                    && k.next() == Some(key)
+            });
+            Some(HostKey {
+                host: pattern.to_string(),
+                key_type: key_type.to_string(),
+                fingerprint: fingerprint(key)?,
+                trusted,
+                key: key.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// How a scan reaches the host: directly, or through the node's jump host
+/// (which then needs the node's identity to authenticate to the bastion).
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanRoute {
+    pub jump_host: Option<String>,
+    pub identity_file: Option<String>,
+    pub identity_secret: Option<String>,
+}
+
+/// Whether the managed known_hosts already holds a key for the jump host.
+fn jump_trusted(jump: &str, known_hosts: &str) -> bool {
+    let (dest, port) = split_jump(jump);
+    let host = dest.rsplit_once('@').map_or(dest, |(_, host)| host);
+    let pattern = known_hosts_pattern(host, port);
+    known_hosts
+        .lines()
+        .any(|line| line.split_whitespace().next().is_some_and(|hosts| hosts.split(',').any(|h| h == pattern)))
+}
+
+/// `ssh` argv that records the target's key into `scratch` through the jump
+/// host. Only the bastion is verified (against the managed file); the target
+/// key is accepted into the scratch file for the user to confirm, and the
+/// `none` auth method makes ssh stop right after the key exchange.
+fn jump_scan_argv(host: &str, port: Option<u16>, jump: &str, files: &SshFiles, scratch: &Path) -> Result<Vec<String>, String> {
+    let mut argv = vec!["ssh".to_string(), "-F".into(), "/dev/null".into()];
+    for opt in [
+        format!("UserKnownHostsFile=\"{}\"", scratch.display()),
+        "GlobalKnownHostsFile=/dev/null".to_string(),
+        "StrictHostKeyChecking=accept-new".to_string(),
+        "HashKnownHosts=no".to_string(),
+        "CheckHostIP=no".to_string(),
+        "BatchMode=yes".to_string(),
+        "PreferredAuthentications=none".to_string(),
+        "ConnectTimeout=10".to_string(),
+        proxy_command(jump, files, 10, files.identity)?,
+    ] {
+        argv.extend(["-o".into(), opt]);
+    }
+    argv.extend(["-p".into(), port.unwrap_or(22).to_string(), "--".into(), host.to_string(), "true".into()]);
+    Ok(argv)
+}
+
+async fn scan(app: &AppHandle, host: &str, port: Option<u16>, route: &ScanRoute) -> Result<(Vec<HostKey>, PathBuf), String> {
+    let host = host_arg("host", host)?;
+    let path = known_hosts_path(app)?;
+    let known = std::fs::read_to_string(&path).unwrap_or_default();
+
+    let offered = match route.jump_host.as_deref().filter(|j| !j.is_empty()) {
+        None => {
+            let output = tokio::process::Command::new("ssh-keyscan")
+                .args(["-T", "10", "-p", &port.unwrap_or(22).to_string(), "--", host])
+                .output()
+                .await
+                .map_err(|e| format!("Failed to run ssh-keyscan: {e}"))?;
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        }
+        Some(jump) => {
+            if !jump_trusted(jump, &known) {
+                return Err(format!("Trust the jump host {jump} before scanning {host} through it"));
+            }
+            let key_file = identity_from_secret(&SshConfig { identity_secret: route.identity_secret.clone(), ..Default::default() })?;
+            let identity = route.identity_file.as_deref().map(Path::new).or(key_file.as_ref().map(SecretFile::path));
+            let scratch = SecretFile::create("known-hosts", b"").map_err(|e| format!("Failed to create scan file: {e}"))?;
+            let files = SshFiles { known_hosts: &path, identity, control_dir: None };
+            let argv = jump_scan_argv(host, port, jump, &files, scratch.path())?;
+            // Authentication is expected to fail; the key is recorded before that
+            let _ = tokio::process::Command::new(&argv[0])
+                .args(&argv[1..])
+                .output()
+                .await
+                .map_err(|e| format!("Failed to run ssh: {e}"))?;
+            std::fs::read_to_string(scratch.path()).unwrap_or_default()
+        }
+    };
+
+    let keys = parse_keyscan(&offered, &known_hosts_pattern(host, port), &known);
+    if keys.is_empty() {
+        return Err(format!("No host keys received from {host}"));
+    }
+    Ok((keys, path))
+}
+
+/// Fetch the host keys a server offers, with fingerprints for the user to verify.
+/// With a jump host, that bastion must be trusted first; the target is then
+/// scanned through it and offers the one key ssh negotiates.
+#[tauri::command]
+pub async fn ssh_scan_host_key(
+    app: AppHandle,
+    host: String,
+    port: Option<u16>,
+    route: Option<ScanRoute>,
+) -> Result<Vec<HostKey>, String> {
+    Ok(scan(&app, &host, port, &route.unwrap_or_default()).await?.0)
+}
+
+/// Trust the key with the given fingerprint. The host is scanned again and the
+/// key must still match what the user approved; it then replaces any previous
+/// key of the same type for that host in the managed known_hosts.
+#[tauri::command]
+pub async fn ssh_trust_host_key(
+    app: AppHandle,
+    host: String,
+    port: Option<u16>,
+    route: Option<ScanRoute>,
+    fingerprint: String,
+) -> Result<HostKey, String> {
+    let (keys, path) = scan(&app, &host, port, &route.unwrap_or_default()).await?;
+    let key = keys
+        .into_iter()
+        .find(|k| k.fingerprint == fingerprint)
+        .ok_or_else(|| format!("{host} no longer offers a key with fingerprint {fingerprint}"))?;
+
+    let existing = std::fs::read_to_string(&path).unwrap_or_default();
+    let mut lines: Vec<&str> = existing
+        .lines()
+        .filter(|line| {
+            let mut parts = line.split_whitespace();
+            !(parts.next() == Some(key.host.as_str()) && parts.next() == Some(key.key_type.as_str()))
+        })
+        .collect();
+    let entry = format!("{} {} {}", key.host, key.key_type, key.key);
+    lines.push(&entry);
+    std::fs::write(&path, lines.join("\n") + "\n").map_err(|e| format!("Failed to update known_hosts: {e}"))?;
+
+    Ok(HostKey { trusted: true, ..key })
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files() -> SshFiles<'static> {
+        SshFiles { known_hosts: Path::new("/tmp/kh"), identity: None, control_dir: None }
+    }
+
+    fn cfg() -> SshConfig {
+        SshConfig {
+            user: Some("deploy".to_string()),
+            host: Some("prod.example.com".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_ssh_wrap_basic() {
+        let result = wrap_for_ssh(&Program::Shell("ls -la".into()), &cfg(), &files(), &[]).unwrap().argv;
+        assert_eq!(result[0], "ssh");
+        assert!(result.contains(&"deploy@prod.example.com".to_string()));
+        assert_eq!(result.last().unwrap(), "sh -c 'ls -la'");
+    }
+
+    #[test]
+    fn test_ssh_wrap_escapes_argv() {
+        let argv = Program::Argv(vec!["echo".into(), "a; b".into()]);
+        let result = wrap_for_ssh(&argv, &cfg(), &files(), &[]).unwrap().argv;
+        assert_eq!(result.last().unwrap(), &format!("sh -c {}", shell_escape("'echo' 'a; b'")));
+    }
+
+    #[test]
+    fn test_ssh_verifies_host_keys_and_requires_host() {
+        let argv = wrap_for_ssh(&Program::Shell("true".into()), &cfg(), &files(), &[]).unwrap().argv;
+        assert!(argv.contains(&"StrictHostKeyChecking=yes".to_string()));
+        assert!(argv.contains(&"UserKnownHostsFile=\"/tmp/kh\"".to_string()));
+        assert!(!argv.iter().any(|a| a.contains("StrictHostKeyChecking=no")));
+
+        let no_host = SshConfig::default();
+        assert!(wrap_for_ssh(&Program::Shell("true".into()), &no_host, &files(), &[]).is_err());
+        let option_host = SshConfig { host: Some("-oProxyCommand=x".into()), ..Default::default() };
+        assert!(wrap_for_ssh(&Program::Shell("true".into()), &option_host, &files(), &[]).is_err());
+    }
+
+    #[test]
+    fn test_known_hosts_path_with_space_is_quoted() {
+        let known_hosts = Path::new("/Users/me/Library/Application Support/devflow/ssh/known_hosts");
+        let files = SshFiles { known_hosts, ..files() };
+        let cfg = SshConfig { jump_host: Some("bastion".into()), ..cfg() };
+        let argv = wrap_for_ssh(&Program::Shell("true".into()), &cfg, &files, &[]).unwrap().argv;
+        let expected = "UserKnownHostsFile=\"/Users/me/Library/Application Support/devflow/ssh/known_hosts\"";
+        assert!(argv.contains(&expected.to_string()));
+        // The bastion hop gets the same single, quoted value
+        let proxy = argv.iter().find(|a| a.starts_with("ProxyCommand=")).unwrap();
+        assert!(proxy.contains(&format!("'{expected}'")), "{proxy}");
+    }
+
+    #[test]
+    fn test_jump_host_must_be_trusted_first() {
+        let known = "[bastion]:2200 ssh-ed25519 AAAA\nother ssh-rsa BBBB\n";
+        assert!(jump_trusted("ops@bastion:2200", known));
+        assert!(!jump_trusted("ops@bastion", known));
+        assert!(!jump_trusted("gateway", known));
+    }
+
+    #[test]
+    fn test_jump_scan_goes_through_verified_bastion() {
+        let scratch = Path::new("/tmp/scan-kh");
+        let argv = jump_scan_argv("10.0.0.5", Some(2222), "ops@bastion", &files(), scratch).unwrap();
+        // Only the scratch file accepts new keys; the bastion hop is strict
+        assert!(argv.contains(&"UserKnownHostsFile=\"/tmp/scan-kh\"".to_string()));
+        assert!(argv.contains(&"StrictHostKeyChecking=accept-new".to_string()));
+        let proxy = argv.iter().find(|a| a.starts_with("ProxyCommand=")).unwrap();
+        assert!(proxy.contains("StrictHostKeyChecking=yes") && proxy.contains("/tmp/kh") && proxy.contains("'ops@bastion'"));
+        assert!(!proxy.contains("accept-new"));
+        assert_eq!(argv[argv.len() - 5..], ["-p", "2222", "--", "10.0.0.5", "true"]);
+    }
+
+    #[test]
+    fn test_ssh_full_options() {
+        let cfg = SshConfig {
+            port: Some(2222),
+            identity_file: Some("/keys/id_ed25519".into()),
+            jump_host: Some("ops@bastion:2200".into()),
+            remote_cwd: Some("/srv/my app".into()),
+            connect_timeout_secs: Some(5),
+            ..cfg()
+        };
+        let argv = wrap_for_ssh(&Program::Shell("make".into()), &cfg, &files(), &[]).unwrap().argv;
+        let joined = argv.join(" ");
+        assert!(joined.contains("-p 2222"));
+        assert!(joined.contains("-i /keys/id_ed25519"));
+        assert!(joined.contains("ConnectTimeout=5"));
+        let proxy = argv.iter().find(|a| a.starts_with("ProxyCommand=")).unwrap();
+        assert!(proxy.contains("'ops@bastion'") && proxy.contains("'2200'") && proxy.contains("StrictHostKeyChecking=yes"));
+        assert_eq!(argv.last().unwrap(), &format!("sh -c {}", shell_escape("cd '/srv/my app' && make")));
+    }
+
+    #[test]
+    fn test_ssh_env_goes_over_stdin() {
+        let forward = vec![("TOKEN".to_string(), Zeroizing::new("it's secret".to_string()))];
+        let inv = wrap_for_ssh(&Program::Shell("deploy.sh".into()), &cfg(), &files(), &forward).unwrap();
+        assert_eq!(inv.argv.last().unwrap(), "sh -s");
+        assert!(!inv.argv.iter().any(|a| a.contains("secret")));
+        assert_eq!(inv.stdin_script.unwrap().as_str(), "export TOKEN='it'\\''s secret'\ndeploy.sh\n");
+
+        let bad = vec![("NOT-VALID".to_string(), Zeroizing::new("x".to_string()))];
+        assert!(wrap_for_ssh(&Program::Shell("x".into()), &cfg(), &files(), &bad).is_err());
+    }
+
+    #[test]
+    fn test_forwards_to_remote_skips_client_vars() {
+        assert!(forwards_to_remote(&SshConfig::default(), "API_TOKEN"));
+        assert!(!forwards_to_remote(&SshConfig::default(), "SSH_AUTH_SOCK"));
+    }
+
+    #[test]
+    fn test_ssh_multiplexes_when_control_dir_set() {
+        let dir = Path::new("/tmp/devflow-ssh-1-1");
+        let files = SshFiles { control_dir: Some(dir), ..files() };
+        let argv = wrap_for_ssh(&Program::Shell("true".into()), &cfg(), &files, &[]).unwrap().argv;
+        assert!(argv.contains(&"ControlMaster=auto".to_string()));
+        assert!(argv.contains(&"ControlPath=/tmp/devflow-ssh-1-1/%C".to_string()));
+
+        let argv = wrap_for_ssh(&Program::Shell("true".into()), &cfg(), &self::files(), &[]).unwrap().argv;
+        assert!(!argv.iter().any(|a| a.starts_with("Control")));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_mux_pool_dir_per_run() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let pool = SshMuxPool::default();
+        let dir = pool.control_dir("run-a").unwrap();
+        assert_eq!(pool.control_dir("run-a"), Some(dir.clone()));
+        assert_ne!(pool.control_dir("run-b"), Some(dir.clone()));
+        assert_eq!(std::fs::metadata(&dir).unwrap().permissions().mode() & 0o777, 0o700);
+
+        assert_eq!(pool.close_run("run-a").await, 0);
+        assert!(!dir.exists());
+        assert_eq!(pool.close_run("run-a").await, 0);
+        pool.close_run("run-b").await;
+    }
+
+    #[test]
+    fn test_parse_keyscan_fingerprints_and_trust() {
+        // Public key blob of a throwaway ed25519 key
+        let key = "AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl";
+        let scan = format!("# example.com:22 SSH-2.0-OpenSSH_9.6\nexample.com ssh-ed25519 {key}\n");
+
+        let keys = parse_keyscan(&scan, "example.com", "");
+        assert_eq!(keys.len(), 1);
+        assert!(keys[0].fingerprint.starts_with("SHA256:"));
+        assert!(!keys[0].fingerprint.ends_with('='));
+        assert!(!keys[0].trusted);
+
+        let known = format!("example.com ssh-ed25519 {key}\n");
+        assert!(parse_keyscan(&scan, "example.com", &known)[0].trusted);
+        assert_eq!(known_hosts_pattern("h", Some(2222)), "[h]:2222");
+        assert_eq!(known_hosts_pattern("h", Some(22)), "h");
+    }
+}
@@ -0,0 +1,141 @@
+// ============================================================
+// DevFlow Studio — Node Config Templating
+// Substitutes `${{ nodes.<id>.outputs.<name> }}` references in
+// a node's config with outputs published by upstream nodes, and
+// `${{ matrix.<key> }}` with a matrix instance's values.
+// Used by the flow engine before a node runs.
+// ============================================================
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::matrix::Combination;
+use super::node_outputs::Outputs;
+
+/// Outputs of every finished node in a run, by node ID.
+pub type RunOutputs = HashMap<String, Outputs>;
+
+/// Render every string in `value`, recursing into arrays and objects.
+/// Object keys are left alone.
+pub fn render(value: &Value, outputs: &RunOutputs) -> Result<Value, String> {
+    map_strings(value, &|s| render_str(s, outputs))
+}
+
+/// Replace each `${{ … }}` in `text`. Text without references is returned unchanged.
+pub fn render_str(text: &str, outputs: &RunOutputs) -> Result<String, String> {
+    substitute(text, |expr| lookup(expr, outputs).map(|v| Some(v.to_string())))
+}
+
+/// Replace `${{ matrix.<key> }}` in every string of `value`. Other references
+/// are kept for `render`, since upstream outputs are only known later.
+pub fn render_matrix(value: &Value, combination: &Combination) -> Result<Value, String> {
+    map_strings(value, &|s| {
+        substitute(s, |expr| {
+            let Some(key) = expr.strip_prefix("matrix.") else { return Ok(None) };
+            combination
+                .get(key)
+                .cloned()
+                .map(Some)
+                .ok_or_else(|| format!("'{expr}': the matrix has no key '{key}'"))
+        })
+    })
+}
+
+fn map_strings(value: &Value, f: &dyn Fn(&str) -> Result<String, String>) -> Result<Value, String> {
+    Ok(match value {
+        Value::String(s) => Value::String(f(s)?),
+        Value::Array(items) => Value::Array(items.iter().map(|v| map_strings(v, f)).collect::<Result<_, _>>()?),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| map_strings(v, f).map(|v| (k.clone(), v)))
+                .collect::<Result<_, String>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+/// Replace each `${{ … }}` with what `resolve` returns for the trimmed
+/// expression; `None` leaves the reference as written.
+fn substitute(text: &str, resolve: impl Fn(&str) -> Result<Option<String>, String>) -> Result<String, String> {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${{") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 3..];
+        let end = after.find("}}").ok_or_else(|| format!("Unterminated '${{{{' in '{text}'"))?;
+        match resolve(after[..end].trim())? {
+            Some(value) => rendered.push_str(&value),
+            None => rendered.push_str(&rest[start..start + 3 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// Resolve `nodes.<id>.outputs.<name>`.
+fn lookup<'a>(expr: &str, outputs: &'a RunOutputs) -> Result<&'a str, String> {
+    let parts: Vec<&str> = expr.split('.').collect();
+    let ["nodes", node, "outputs", name] = parts[..] else {
+        return Err(format!("Unsupported expression '{expr}': expected nodes.<id>.outputs.<name>"));
+    };
+    let node_outputs = outputs
+        .get(node)
+        .ok_or_else(|| format!("'{expr}': node '{node}' has not run or published no outputs"))?;
+    node_outputs
+        .get(name)
+        .map(String::as_str)
+        .ok_or_else(|| format!("'{expr}': node '{node}' has no output '{name}'"))
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn outputs() -> RunOutputs {
+        let build = Outputs::from([("digest".to_string(), "sha256:abc".to_string())]);
+        RunOutputs::from([("build".to_string(), build)])
+    }
+
+    #[test]
+    fn test_render_references() {
+        let config = json!({
+            "image": "app@${{ nodes.build.outputs.digest }}",
+            "args": ["--digest=${{nodes.build.outputs.digest}}", 3],
+            "nested": { "same": "${{ nodes.build.outputs.digest }}" },
+            "plain": "no refs $HOME ${VAR}",
+        });
+        let rendered = render(&config, &outputs()).unwrap();
+        assert_eq!(rendered["image"], "app@sha256:abc");
+        assert_eq!(rendered["args"], json!(["--digest=sha256:abc", 3]));
+        assert_eq!(rendered["nested"]["same"], "sha256:abc");
+        assert_eq!(rendered["plain"], "no refs $HOME ${VAR}");
+    }
+
+    #[test]
+    fn test_render_errors() {
+        let err = render_str("${{ nodes.test.outputs.digest }}", &outputs()).unwrap_err();
+        assert!(err.contains("node 'test'"), "{err}");
+        let err = render_str("${{ nodes.build.outputs.tag }}", &outputs()).unwrap_err();
+        assert!(err.contains("no output 'tag'"), "{err}");
+        assert!(render_str("${{ env.HOME }}", &outputs()).unwrap_err().contains("Unsupported"));
+        assert!(render_str("${{ nodes.build.outputs.digest", &outputs()).unwrap_err().contains("Unterminated"));
+    }
+
+    #[test]
+    fn test_render_matrix_keeps_output_refs() {
+        let combination = Combination::from([("node".to_string(), "20".to_string())]);
+        let config = json!({ "image": "node:${{ matrix.node }}", "tag": "${{ nodes.build.outputs.digest }}" });
+        let rendered = render_matrix(&config, &combination).unwrap();
+        assert_eq!(rendered["image"], "node:20");
+        assert_eq!(rendered["tag"], "${{ nodes.build.outputs.digest }}");
+        assert_eq!(render(&rendered, &outputs()).unwrap()["tag"], "sha256:abc");
+
+        let err = render_matrix(&json!("${{ matrix.os }}"), &combination).unwrap_err();
+        assert!(err.contains("no key 'os'"), "{err}");
+    }
+}
@@ -0,0 +1,225 @@
+// ============================================================
+// DevFlow Studio — Process Termination Details
+// Why a node's process ended: a normal exit, a signal (with
+// core dump flag), a timeout, a cancellation or the OOM killer.
+// `exit_code` alone reports all but the first as -1.
+// ============================================================
+
+use std::fmt;
+use std::process::ExitStatus;
+
+use serde::{Deserialize, Serialize};
+
+/// How a node's process ended.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Termination {
+    Exited { code: i32 },
+    /// Killed by a signal, e.g. `SIGSEGV`. For containers this is inferred
+    /// from a `128 + n` exit code, so `core_dumped` is always false there.
+    Signaled { signal: String, core_dumped: bool },
+    TimedOut,
+    Cancelled,
+    /// Killed by the kernel after exceeding the node's memory limit.
+    OomKilled,
+}
+
+/// What the executor observed besides the exit status itself.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StopCause {
+    pub timed_out: bool,
+    pub cancelled: bool,
+    pub oom_killed: bool,
+    /// The status is that of a `docker run` / `podman run` client.
+    pub container: bool,
+}
+
+impl Termination {
+    pub fn from_status(status: &ExitStatus, cause: StopCause) -> Self {
+        // Our own interventions explain a signal better than the signal does
+        if cause.timed_out {
+            return Termination::TimedOut;
+        }
+        if cause.cancelled {
+            return Termination::Cancelled;
+        }
+        if cause.oom_killed {
+            return Termination::OomKilled;
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            if let Some(signal) = status.signal() {
+                return Termination::Signaled { signal: signal_name(signal), core_dumped: status.core_dumped() };
+            }
+        }
+
+        let code = status.code().unwrap_or(-1);
+        if cause.container {
+            return Self::from_container_code(code);
+        }
+        Termination::Exited { code }
+    }
+
+    /// `docker run` exits with `128 + n` when the containerised process died
+    /// from signal `n` (137 = SIGKILL, 139 = SIGSEGV). 125–127 are the
+    /// client's own errors and stay plain exits.
+    pub fn from_container_code(code: i32) -> Self {
+        match code.checked_sub(128).and_then(linux_signal_name) {
+            Some(signal) => Termination::Signaled { signal: signal.to_string(), core_dumped: false },
+            None => Termination::Exited { code },
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        *self == Termination::Exited { code: 0 }
+    }
+
+    /// Success, or a normal exit with one of the node's `allowed` codes.
+    pub fn allows(&self, allowed: &[i32]) -> bool {
+        match self {
+            Termination::Exited { code } => *code == 0 || allowed.contains(code),
+            _ => false,
+        }
+    }
+
+    /// Whether the cause is environmental and a retry may succeed: timeouts,
+    /// memory pressure, or being killed from outside. Exit codes, crashes
+    /// (SIGSEGV, SIGABRT, …) and user cancellation are not.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Termination::TimedOut | Termination::OomKilled => true,
+            Termination::Signaled { signal, .. } => {
+                matches!(signal.as_str(), "SIGKILL" | "SIGTERM" | "SIGHUP" | "SIGINT" | "SIGPIPE")
+            }
+            Termination::Exited { .. } | Termination::Cancelled => false,
+        }
+    }
+}
+
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Termination::Exited { code } => write!(f, "Exit code {code}"),
+            Termination::Signaled { signal, core_dumped: true } => write!(f, "Killed by {signal} (core dumped)"),
+            Termination::Signaled { signal, .. } => write!(f, "Killed by {signal}"),
+            Termination::TimedOut => f.write_str("Command timed out"),
+            Termination::Cancelled => f.write_str("Cancelled"),
+            Termination::OomKilled => f.write_str("Out of memory (OOM killed)"),
+        }
+    }
+}
+
+/// Name of a signal number on this host.
+#[cfg(unix)]
+fn signal_name(signal: i32) -> String {
+    let name = match signal {
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGINT => "SIGINT",
+        libc::SIGQUIT => "SIGQUIT",
+        libc::SIGILL => "SIGILL",
+        libc::SIGTRAP => "SIGTRAP",
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGUSR1 => "SIGUSR1",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGUSR2 => "SIGUSR2",
+        libc::SIGPIPE => "SIGPIPE",
+        libc::SIGALRM => "SIGALRM",
+        libc::SIGTERM => "SIGTERM",
+        libc::SIGXCPU => "SIGXCPU",
+        libc::SIGXFSZ => "SIGXFSZ",
+        libc::SIGSYS => "SIGSYS",
+        other => return format!("SIG{other}"),
+    };
+    name.to_string()
+}
+
+/// Signal names by Linux number, for codes reported from inside containers
+/// (which run Linux even when the host doesn't).
+fn linux_signal_name(signal: i32) -> Option<&'static str> {
+    const NAMES: [&str; 31] = [
+        "SIGHUP", "SIGINT", "SIGQUIT", "SIGILL", "SIGTRAP", "SIGABRT", "SIGBUS", "SIGFPE", "SIGKILL", "SIGUSR1",
+        "SIGSEGV", "SIGUSR2", "SIGPIPE", "SIGALRM", "SIGTERM", "SIGSTKFLT", "SIGCHLD", "SIGCONT", "SIGSTOP",
+        "SIGTSTP", "SIGTTIN", "SIGTTOU", "SIGURG", "SIGXCPU", "SIGXFSZ", "SIGVTALRM", "SIGPROF", "SIGWINCH",
+        "SIGIO", "SIGPWR", "SIGSYS",
+    ];
+    usize::try_from(signal).ok().filter(|&n| n >= 1).and_then(|n| NAMES.get(n - 1).copied())
+}
+
+// ── Unit tests ─────────────────────────────────────────────────────────────────
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    #[test]
+    fn test_exit_and_signal() {
+        let exited = ExitStatus::from_raw(3 << 8);
+        assert_eq!(Termination::from_status(&exited, StopCause::default()), Termination::Exited { code: 3 });
+
+        // Raw wait status: signal 11 with the core dump bit (0x80)
+        let segv = ExitStatus::from_raw(libc::SIGSEGV | 0x80);
+        assert_eq!(
+            Termination::from_status(&segv, StopCause::default()),
+            Termination::Signaled { signal: "SIGSEGV".into(), core_dumped: true }
+        );
+    }
+
+    #[test]
+    fn test_causes_take_precedence() {
+        let killed = ExitStatus::from_raw(libc::SIGKILL);
+        let cause = StopCause { timed_out: true, ..Default::default() };
+        assert_eq!(Termination::from_status(&killed, cause), Termination::TimedOut);
+        let cause = StopCause { oom_killed: true, ..Default::default() };
+        assert_eq!(Termination::from_status(&killed, cause), Termination::OomKilled);
+    }
+
+    #[test]
+    fn test_container_codes() {
+        let container = StopCause { container: true, ..Default::default() };
+        assert_eq!(
+            Termination::from_status(&ExitStatus::from_raw(137 << 8), container),
+            Termination::Signaled { signal: "SIGKILL".into(), core_dumped: false }
+        );
+        assert_eq!(
+            Termination::from_container_code(139),
+            Termination::Signaled { signal: "SIGSEGV".into(), core_dumped: false }
+        );
+        assert_eq!(Termination::from_container_code(125), Termination::Exited { code: 125 });
+        // Outside a container, 137 is just an exit code
+        assert_eq!(
+            Termination::from_status(&ExitStatus::from_raw(137 << 8), StopCause::default()),
+            Termination::Exited { code: 137 }
+        );
+    }
+
+    #[test]
+    fn test_allowed_exit_codes() {
+        assert!(Termination::Exited { code: 0 }.allows(&[]));
+        assert!(Termination::Exited { code: 1 }.allows(&[1, 2]));
+        assert!(!Termination::Exited { code: 3 }.allows(&[1, 2]));
+        assert!(!Termination::TimedOut.allows(&[1]));
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(Termination::TimedOut.is_transient());
+        assert!(Termination::OomKilled.is_transient());
+        assert!(Termination::Signaled { signal: "SIGKILL".into(), core_dumped: false }.is_transient());
+        assert!(!Termination::Signaled { signal: "SIGSEGV".into(), core_dumped: true }.is_transient());
+        assert!(!Termination::Exited { code: 1 }.is_transient());
+        assert!(!Termination::Cancelled.is_transient());
+    }
+
+    #[test]
+    fn test_serializes_with_kind_tag() {
+        let json = serde_json::to_value(Termination::Signaled { signal: "SIGSEGV".into(), core_dumped: true }).unwrap();
+        assert_eq!(json, serde_json::json!({ "kind": "signaled", "signal": "SIGSEGV", "core_dumped": true }));
+        assert_eq!(serde_json::to_value(Termination::OomKilled).unwrap(), serde_json::json!({ "kind": "oom_killed" }));
+    }
+}
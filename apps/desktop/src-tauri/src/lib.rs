@@ -1,9 +1,16 @@
 // DevFlow Studio — Tauri Library (lib.rs)
 
 pub mod commands;
-use commands::executor::execute_command;
+use commands::executor::{execute_command, cancel_execution};
+use commands::docker::probe_container_runtime;
+use commands::expression::validate_expression;
+use commands::flow_engine::{run_flow, get_run_status, cancel_run, resume_node, FlowRuns};
+use commands::metrics::MetricsSampler;
+use commands::process::ProcessRegistry;
+use commands::retry::{approve_retry, RetryGate};
 use commands::detector::detect_project;
 use commands::folders::{pick_folder, save_flow, load_flow};
+use commands::ssh::{ssh_scan_host_key, ssh_trust_host_key, SshMuxPool};
 use commands::secure_storage::{store_secret, get_secret, delete_secret, secret_exists};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -14,11 +21,29 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_sql::Builder::default().build())
+        .manage(ProcessRegistry::default())
+        .manage(MetricsSampler::default())
+        .manage(FlowRuns::default())
+        .manage(SshMuxPool::default())
+        .manage(RetryGate::default())
         .invoke_handler(tauri::generate_handler![
             // Execution
             execute_command,
+            cancel_execution,
+            approve_retry,
+            // Flow engine
+            run_flow,
+            get_run_status,
+            cancel_run,
+            resume_node,
+            // Condition expressions
+            validate_expression,
+            // SSH host key trust
+            ssh_scan_host_key,
+            ssh_trust_host_key,
             // Project detection
             detect_project,
+            probe_container_runtime,
             // File I/O
             pick_folder,
             save_flow,
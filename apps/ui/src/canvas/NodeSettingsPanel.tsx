@@ -3,10 +3,11 @@
 // Dynamically renders form fields based on NodeDefinition schema.
 // ============================================================
 
-import type { ChangeEvent } from 'react';
+import { useEffect, useState, type ChangeEvent } from 'react';
 import { useFlowStore } from '../store/flowStore.ts';
 import { getNodeDef } from '../lib/nodeRegistry.ts';
 import { X, Trash2 } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
 
 function TextInput({
     label,
@@ -117,6 +118,96 @@ function SelectInput({
     );
 }
 
+interface ExprError {
+    message: string;
+    /** 1-based character column */
+    column: number;
+}
+
+/** Condition input that validates as you type and points at the error column. */
+function ExpressionInput({
+    label,
+    value,
+    onChange,
+    placeholder,
+}: {
+    label: string;
+    value: string;
+    onChange: (v: string) => void;
+    placeholder?: string;
+}) {
+    const [error, setError] = useState<ExprError | null>(null);
+
+    useEffect(() => {
+        let stale = false;
+        const timer = setTimeout(() => {
+            invoke<ExprError | null>('validate_expression', { expression: value })
+                .then(err => { if (!stale) setError(err); })
+                .catch(() => {});
+        }, 200);
+        return () => { stale = true; clearTimeout(timer); };
+    }, [value]);
+
+    return (
+        <div className="flex flex-col gap-1">
+            <TextInput label={label} value={value} onChange={onChange} placeholder={placeholder} mono />
+            {error && (
+                <div className="font-mono text-xs text-red-400 overflow-x-auto">
+                    <pre className="text-gray-500">{value}</pre>
+                    <pre>{' '.repeat(Math.max(error.column - 1, 0))}^</pre>
+                    <p className="font-sans">{error.message} (column {error.column})</p>
+                </div>
+            )}
+        </div>
+    );
+}
+
+interface HostKey {
+    host: string;
+    keyType: string;
+    fingerprint: string;
+    trusted: boolean;
+}
+
+/** How ssh.rs reaches a host for a scan: directly or through a jump host. */
+interface ScanRoute {
+    jumpHost?: string;
+    identityFile?: string;
+    identitySecret?: string;
+}
+
+/** Scan one host and pin each key the user confirms. Returns false if declined. */
+async function trustHost(host: string, port: number | null, route: ScanRoute | null): Promise<boolean> {
+    const keys = await invoke<HostKey[]>('ssh_scan_host_key', { host, port, route });
+    for (const key of keys.filter(k => !k.trusted)) {
+        if (!confirm(`Trust ${key.keyType} key for ${key.host}?\n\n${key.fingerprint}\n\nCompare this fingerprint with the server before accepting.`)) return false;
+        await invoke('ssh_trust_host_key', { host, port, route, fingerprint: key.fingerprint });
+    }
+    return true;
+}
+
+/** Scan an SSH host and, once the user confirms the fingerprint, pin it.
+ *  A jump host is verified first, then the target is scanned through it. */
+async function verifyHostKey(host: string | undefined, port: number | undefined, route: ScanRoute) {
+    if (!host) {
+        alert('Set the SSH host first.');
+        return;
+    }
+    try {
+        if (route.jumpHost) {
+            // [user@]host[:port]
+            const [, jumpHost, jumpPort] = route.jumpHost.match(/^(?:[^@]*@)?([^:]+)(?::(\d+))?$/) ?? [];
+            if (!jumpHost) throw new Error(`Invalid jump host '${route.jumpHost}'`);
+            if (!await trustHost(jumpHost, jumpPort ? parseInt(jumpPort) : null, null)) return;
+        }
+        if (await trustHost(host, port || null, route.jumpHost ? route : null)) {
+            alert(`Host keys for ${host} are trusted.`);
+        }
+    } catch (err) {
+        alert(`Host key verification failed: ${err}`);
+    }
+}
+
 export function NodeSettingsPanel() {
     const { nodes, selectedNodeId, setSelectedNode, updateNodeConfig, removeNode } = useFlowStore();
     const selectedNode = nodes.find(n => n.id === selectedNodeId);
@@ -163,6 +254,18 @@ export function NodeSettingsPanel() {
                     {def?.configSchema.map(field => {
                         const value = cfg[field.key];
 
+                        if (field.key === 'condition' && selectedNode.data.nodeType === 'conditionalNode') {
+                            return (
+                                <ExpressionInput
+                                    key={field.key}
+                                    label={field.label}
+                                    value={String(value ?? '')}
+                                    placeholder={field.placeholder}
+                                    onChange={v => update(field.key, v)}
+                                />
+                            );
+                        }
+
                         if (field.type === 'text') {
                             let displayValue = '';
                             if (Array.isArray(value)) displayValue = value.join(', ');
@@ -238,7 +341,7 @@ export function NodeSettingsPanel() {
                         onChange={v => update('retryPolicy', { ...(cfg.retryPolicy as any), strategy: v })}
                     />
 
-                    {['auto', 'exponential'].includes((cfg.retryPolicy as any)?.strategy) && (
+                    {['auto', 'exponential', 'manual'].includes((cfg.retryPolicy as any)?.strategy) && (
                         <TextInput
                             label="Max Attempts"
                             value={String((cfg.retryPolicy as any)?.maxAttempts ?? 2)}
@@ -247,14 +350,107 @@ export function NodeSettingsPanel() {
                     )}
 
                     {((cfg.retryPolicy as any)?.strategy === 'exponential') && (
-                        <TextInput
-                            label="Base Backoff (ms)"
-                            value={String((cfg.retryPolicy as any)?.backoffMs ?? 1000)}
-                            onChange={v => update('retryPolicy', { ...(cfg.retryPolicy as any), backoffMs: parseInt(v) || 1000 })}
-                        />
+                        <>
+                            <TextInput
+                                label="Base Backoff (ms)"
+                                value={String((cfg.retryPolicy as any)?.backoffMs ?? 1000)}
+                                onChange={v => update('retryPolicy', { ...(cfg.retryPolicy as any), backoffMs: parseInt(v) || 1000 })}
+                            />
+                            <TextInput
+                                label="Max Delay (ms)"
+                                value={String((cfg.retryPolicy as any)?.maxDelayMs ?? 30000)}
+                                onChange={v => update('retryPolicy', { ...(cfg.retryPolicy as any), maxDelayMs: parseInt(v) || 30000 })}
+                            />
+                            <Toggle
+                                label="Jitter"
+                                checked={(cfg.retryPolicy as any)?.jitter ?? true}
+                                onChange={v => update('retryPolicy', { ...(cfg.retryPolicy as any), jitter: v })}
+                            />
+                        </>
+                    )}
+
+                    {['auto', 'exponential', 'manual'].includes((cfg.retryPolicy as any)?.strategy) && (
+                        <>
+                            <p className="text-[10px] text-gray-500">Retry only on (any failure when none set):</p>
+                            <TextInput
+                                label="Exit Codes"
+                                placeholder="e.g. 75, 137"
+                                mono
+                                value={((cfg.retryPolicy as any)?.retryOn?.exitCodes || []).join(', ')}
+                                onChange={v => update('retryPolicy', {
+                                    ...(cfg.retryPolicy as any),
+                                    retryOn: {
+                                        ...(cfg.retryPolicy as any)?.retryOn,
+                                        exitCodes: v.split(',').map(s => parseInt(s.trim())).filter(n => !isNaN(n)),
+                                    },
+                                })}
+                            />
+                            <TextInput
+                                label="Stderr Matches (regex)"
+                                placeholder="connection (reset|refused)"
+                                mono
+                                value={(cfg.retryPolicy as any)?.retryOn?.stderrPattern || ''}
+                                onChange={v => update('retryPolicy', { ...(cfg.retryPolicy as any), retryOn: { ...(cfg.retryPolicy as any)?.retryOn, stderrPattern: v } })}
+                            />
+                            <Toggle
+                                label="Timed Out"
+                                checked={!!(cfg.retryPolicy as any)?.retryOn?.timedOut}
+                                onChange={v => update('retryPolicy', { ...(cfg.retryPolicy as any), retryOn: { ...(cfg.retryPolicy as any)?.retryOn, timedOut: v } })}
+                            />
+                            <Toggle
+                                label="Out of Memory"
+                                checked={!!(cfg.retryPolicy as any)?.retryOn?.oom}
+                                onChange={v => update('retryPolicy', { ...(cfg.retryPolicy as any), retryOn: { ...(cfg.retryPolicy as any)?.retryOn, oom: v } })}
+                            />
+                            <Toggle
+                                label="Transient (timeout, OOM, killed)"
+                                checked={!!(cfg.retryPolicy as any)?.retryOn?.transient}
+                                onChange={v => update('retryPolicy', { ...(cfg.retryPolicy as any), retryOn: { ...(cfg.retryPolicy as any)?.retryOn, transient: v } })}
+                            />
+                        </>
                     )}
                 </div>
 
+                {/* ── Failure Handling ── */}
+                <div className="pt-4 border-t border-canvas-border flex flex-col gap-3">
+                    <h3 className="text-[10px] font-semibold text-gray-400 uppercase tracking-wider flex items-center gap-1.5"><span className="text-orange-400">⚑</span> Failure Handling</h3>
+
+                    <TextInput
+                        label="Allowed Exit Codes"
+                        placeholder="e.g. 1, 3"
+                        mono
+                        value={((cfg.allowedExitCodes as number[] | undefined) || []).join(', ')}
+                        onChange={v => update('allowedExitCodes', v.split(',').map(s => parseInt(s.trim())).filter(n => !isNaN(n)))}
+                    />
+                    <Toggle
+                        label="Continue On Error"
+                        checked={!!cfg.continueOnError}
+                        onChange={v => update('continueOnError', v)}
+                    />
+                    <SelectInput
+                        label="Run When"
+                        value={String(cfg.runWhen || 'on_success')}
+                        options={['on_success', 'on_failure', 'always']}
+                        onChange={v => update('runWhen', v)}
+                    />
+                </div>
+
+                {/* ── Matrix ── */}
+                <div className="pt-4 border-t border-canvas-border flex flex-col gap-3">
+                    <h3 className="text-[10px] font-semibold text-gray-400 uppercase tracking-wider flex items-center gap-1.5"><span className="text-purple-400">▦</span> Matrix</h3>
+
+                    <TextArea
+                        label="Matrix (JSON)"
+                        placeholder={'{ "node": [18, 20, 22], "exclude": [], "include": [], "failFast": true, "maxParallel": 2 }'}
+                        mono
+                        value={typeof cfg.matrix === 'string' ? cfg.matrix : cfg.matrix ? JSON.stringify(cfg.matrix, null, 2) : ''}
+                        onChange={v => update('matrix', v)}
+                    />
+                    <p className="text-[10px] text-gray-500">
+                        One instance per combination. Values are exposed as MATRIX_&lt;KEY&gt; env vars and as {'${{ matrix.<key> }}'} in this node's config.
+                    </p>
+                </div>
+
                 {/* ── Execution Profile ── */}
                 <div className="pt-4 border-t border-canvas-border flex flex-col gap-3">
                     <h3 className="text-[10px] font-semibold text-gray-400 uppercase tracking-wider flex items-center gap-1.5"><span className="text-blue-400">⚡</span> Execution Profile</h3>
@@ -262,7 +458,7 @@ export function NodeSettingsPanel() {
                     <SelectInput
                         label="Sandbox Environment"
                         value={(cfg.executionProfile as any)?.profile || 'native'}
-                        options={['native', 'docker', 'ssh']}
+                        options={['native', 'docker', 'ssh', 'sandbox']}
                         onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), profile: v })}
                     />
 
@@ -272,11 +468,36 @@ export function NodeSettingsPanel() {
                         onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), timeoutSeconds: parseInt(v) || 300 })}
                     />
 
+                    {['native', 'sandbox', undefined].includes((cfg.executionProfile as any)?.profile) && (
+                        <>
+                            <TextInput label="CPU Limit (cores, e.g. 1.5)" placeholder="no limit" value={(cfg.executionProfile as any)?.cpuLimit || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), cpuLimit: v })} />
+                            <TextInput label="Mem Limit (e.g. 2g)" placeholder="no limit" value={(cfg.executionProfile as any)?.memLimit || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), memLimit: v })} />
+                            <TextInput label="Max Processes" placeholder="no limit" value={String((cfg.executionProfile as any)?.pidsLimit || '')} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), pidsLimit: parseInt(v) || undefined })} />
+                            <TextInput label="IO Weight (1-10000)" placeholder="100" value={String((cfg.executionProfile as any)?.ioWeight || '')} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), ioWeight: parseInt(v) || undefined })} />
+                        </>
+                    )}
+
                     {((cfg.executionProfile as any)?.profile === 'docker') && (
                         <>
+                            <SelectInput label="Container Runtime" value={(cfg.executionProfile as any)?.dockerRuntime || 'auto'} options={['auto', 'docker', 'podman']} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), dockerRuntime: v === 'auto' ? '' : v })} />
                             <TextInput label="Docker Image" placeholder="ubuntu:22.04" value={(cfg.executionProfile as any)?.dockerImage || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), dockerImage: v })} />
                             <TextInput label="CPU Limit (e.g. 0.5)" placeholder="no limit" value={(cfg.executionProfile as any)?.cpuLimit || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), cpuLimit: v })} />
                             <TextInput label="Mem Limit (e.g. 512m)" placeholder="no limit" value={(cfg.executionProfile as any)?.memLimit || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), memLimit: v })} />
+                            <TextInput label="Forward Env (comma-sep, PREFIX_* ok)" placeholder="all except DOCKER_*" value={(cfg.executionProfile as any)?.dockerEnvAllowlist || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), dockerEnvAllowlist: v })} />
+                            <TextArea label="Extra Volumes (host:container[:ro], one per line)" placeholder="/data:/data:ro" mono value={(cfg.executionProfile as any)?.dockerVolumes || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), dockerVolumes: v })} />
+                            <TextInput label="Network" placeholder="bridge / host / none / my-net" value={(cfg.executionProfile as any)?.dockerNetwork || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), dockerNetwork: v })} />
+                            <Toggle label="Run as host user (UID:GID)" checked={(cfg.executionProfile as any)?.dockerUser === 'host'} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), dockerUser: v ? 'host' : '' })} />
+                            <SelectInput label="Pull Policy" value={(cfg.executionProfile as any)?.dockerPull || 'missing'} options={['missing', 'always', 'never']} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), dockerPull: v })} />
+                            <TextInput label="Entrypoint Override" placeholder="image default" value={(cfg.executionProfile as any)?.dockerEntrypoint || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), dockerEntrypoint: v })} />
+                            <TextInput label="Shm Size (e.g. 1g)" placeholder="docker default" value={(cfg.executionProfile as any)?.dockerShmSize || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), dockerShmSize: v })} />
+                            <SelectInput label="SELinux Mount Label" value={(cfg.executionProfile as any)?.dockerSelinuxLabel || 'auto'} options={['auto', 'private', 'shared', 'none']} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), dockerSelinuxLabel: v === 'auto' ? '' : v })} />
+                        </>
+                    )}
+
+                    {((cfg.executionProfile as any)?.profile === 'sandbox') && (
+                        <>
+                            <Toggle label="Allow Network" checked={!!(cfg.executionProfile as any)?.sandboxNetwork} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), sandboxNetwork: v })} />
+                            <TextArea label="Extra Writable Paths (one per line)" placeholder="project dir only" mono value={(cfg.executionProfile as any)?.sandboxReadWrite || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), sandboxReadWrite: v })} />
                         </>
                     )}
 
@@ -284,6 +505,23 @@ export function NodeSettingsPanel() {
                         <>
                             <TextInput label="SSH User" placeholder="root" value={(cfg.executionProfile as any)?.sshUser || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), sshUser: v })} />
                             <TextInput label="SSH Host" placeholder="example.com" value={(cfg.executionProfile as any)?.sshHost || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), sshHost: v })} />
+                            <TextInput label="SSH Port" placeholder="22" value={String((cfg.executionProfile as any)?.sshPort || '')} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), sshPort: parseInt(v) || undefined })} />
+                            <TextInput label="Identity File" placeholder="~/.ssh/id_ed25519" value={(cfg.executionProfile as any)?.sshIdentityFile || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), sshIdentityFile: v })} />
+                            <TextInput label="Identity Secret (keychain key)" placeholder="DEPLOY_KEY" value={(cfg.executionProfile as any)?.sshIdentitySecret || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), sshIdentitySecret: v })} />
+                            <TextInput label="Jump Host ([user@]host[:port])" placeholder="none" value={(cfg.executionProfile as any)?.sshJumpHost || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), sshJumpHost: v })} />
+                            <TextInput label="Remote Working Dir" placeholder="login directory" value={(cfg.executionProfile as any)?.sshRemoteCwd || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), sshRemoteCwd: v })} />
+                            <TextInput label="Forward Env (comma-sep, PREFIX_* ok)" placeholder="all except SSH_*" value={(cfg.executionProfile as any)?.sshEnvAllowlist || ''} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), sshEnvAllowlist: v })} />
+                            <TextInput label="Connect Timeout (seconds)" placeholder="15" value={String((cfg.executionProfile as any)?.sshConnectTimeout || '')} onChange={v => update('executionProfile', { ...(cfg.executionProfile as any), sshConnectTimeout: parseInt(v) || undefined })} />
+                            <button
+                                onClick={() => verifyHostKey((cfg.executionProfile as any)?.sshHost, (cfg.executionProfile as any)?.sshPort, {
+                                    jumpHost: (cfg.executionProfile as any)?.sshJumpHost || undefined,
+                                    identityFile: (cfg.executionProfile as any)?.sshIdentityFile || undefined,
+                                    identitySecret: (cfg.executionProfile as any)?.sshIdentitySecret || undefined,
+                                })}
+                                className="w-full px-3 py-1.5 rounded-lg bg-canvas-bg border border-canvas-border text-slate-300 hover:text-white text-xs font-medium"
+                            >
+                                Verify Host Key
+                            </button>
                         </>
                     )}
                 </div>
@@ -55,7 +55,7 @@ export function Toolbar() {
         executionCheckpoint
     } = useFlowStore();
 
-    const { runFlow, isRunning } = useFlowExecution();
+    const { runFlow, stopFlow, isRunning } = useFlowExecution();
     const { setProject, projectPath } = useProjectStore();
 
     const [isHistoryOpen, setHistoryOpen] = useState(false);
@@ -84,6 +84,7 @@ export function Toolbar() {
                 hasRequirements: result.has_requirements,
                 hasDockerCompose: result.has_docker_compose,
                 packageScripts: result.package_scripts,
+                containerRuntime: result.container_runtime?.runtime ?? null,
             });
         } catch (err) {
             toast.error(`Error: ${err}`, { id: 'open-folder' });
@@ -318,10 +319,11 @@ export function Toolbar() {
                 <span className="hidden md:block">Dry Run</span>
             </button>
 
-            {/* Run Flow */}
-            <button onClick={() => handleRun()} disabled={nodes.length === 0}
-                className={`flex items-center gap-2 px-4 py-1.5 rounded-lg font-semibold text-sm transition-all flex-shrink-0 ${isRunning ? 'bg-amber-600/20 text-amber-400 cursor-not-allowed' : 'bg-blue-600 hover:bg-blue-500 text-white'}`}>
-                {isRunning ? <><StopCircle size={14} className="animate-pulse" /> Running...</> : <><Play size={14} /> Run Flow</>}
+            {/* Run Flow / Stop */}
+            <button onClick={() => isRunning ? stopFlow() : handleRun()} disabled={nodes.length === 0}
+                title={isRunning ? 'Stop the flow and kill its running nodes' : undefined}
+                className={`flex items-center gap-2 px-4 py-1.5 rounded-lg font-semibold text-sm transition-all flex-shrink-0 ${isRunning ? 'bg-red-600/20 text-red-400 hover:bg-red-600/30' : 'bg-blue-600 hover:bg-blue-500 text-white'}`}>
+                {isRunning ? <><StopCircle size={14} className="animate-pulse" /> Stop</> : <><Play size={14} /> Run Flow</>}
             </button>
 
             {/* Modals */}
@@ -11,10 +11,13 @@ import { getNodeDef } from '../../lib/nodeRegistry.ts';
 
 const STATUS_BADGE: Record<NodeStatus, { text: string; cls: string }> = {
     idle: { text: 'idle', cls: 'bg-gray-700 text-gray-400' },
+    paused: { text: 'paused', cls: 'bg-blue-500/20 text-blue-400' },
     running: { text: 'running', cls: 'bg-amber-500/20 text-amber-400 animate-pulse' },
     success: { text: 'done', cls: 'bg-green-500/20 text-green-400' },
+    success_with_warnings: { text: 'warnings', cls: 'bg-yellow-500/20 text-yellow-400' },
     error: { text: 'error', cls: 'bg-red-500/20 text-red-400' },
     skipped: { text: 'skipped', cls: 'bg-gray-600/20 text-gray-500' },
+    cancelled: { text: 'stopped', cls: 'bg-orange-500/20 text-orange-400' },
 };
 
 function DevFlowNodeInner({ data, selected }: NodeProps<DevFlowNodeData>) {
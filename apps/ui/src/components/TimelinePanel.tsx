@@ -6,7 +6,7 @@
 import { useFlowStore, type NodeExecutionRecord } from '../store/flowStore.ts';
 import { getNodeDef } from '../lib/nodeRegistry.ts';
 import { useFlowExecution } from '../hooks/useFlowExecution.tsx';
-import { Clock, CheckCircle2, XCircle, PlayCircle, MinusCircle, RotateCcw } from 'lucide-react';
+import { Clock, CheckCircle2, AlertTriangle, XCircle, PlayCircle, PauseCircle, MinusCircle, StopCircle, RotateCcw } from 'lucide-react';
 
 function TimelinePill({ record }: { record: NodeExecutionRecord }) {
     const def = getNodeDef(record.nodeType);
@@ -14,10 +14,13 @@ function TimelinePill({ record }: { record: NodeExecutionRecord }) {
 
     const statusMap = {
         idle: { icon: <MinusCircle size={12} />, cls: 'bg-gray-800 text-gray-500 border-gray-700' },
+        paused: { icon: <PauseCircle size={12} />, cls: 'bg-blue-500/10 text-blue-400 border-blue-500/30' },
         running: { icon: <PlayCircle size={12} className="animate-pulse" />, cls: 'bg-amber-500/10 text-amber-400 border-amber-500/30' },
         success: { icon: <CheckCircle2 size={12} />, cls: 'bg-green-500/10 text-green-400 border-green-500/30' },
+        success_with_warnings: { icon: <AlertTriangle size={12} />, cls: 'bg-yellow-500/10 text-yellow-400 border-yellow-500/30' },
         error: { icon: <XCircle size={12} />, cls: 'bg-red-500/10 text-red-400 border-red-500/30' },
         skipped: { icon: <MinusCircle size={12} />, cls: 'bg-gray-800 text-gray-600 border-gray-700' },
+        cancelled: { icon: <StopCircle size={12} />, cls: 'bg-orange-500/10 text-orange-400 border-orange-500/30' },
     };
 
     const s = statusMap[record.status] || statusMap.idle;
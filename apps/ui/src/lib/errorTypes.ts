@@ -8,6 +8,17 @@
 /** How the executor handles a failed node. */
 export type RetryStrategy = 'none' | 'manual' | 'auto' | 'exponential';
 
+/** Failures worth retrying; with nothing set, any failure is (see retry.rs). */
+export interface RetryOn {
+    exitCodes?: number[];
+    timedOut?: boolean;
+    oom?: boolean;
+    /** Regex matched against the attempt's stderr */
+    stderrPattern?: string;
+    /** Timeouts, OOM kills and SIGKILL/SIGTERM-style signals */
+    transient?: boolean;
+}
+
 /** Per-node retry configuration stored in node.data.config.retryPolicy */
 export interface RetryPolicy {
     /** Which retry mode to use. Default: 'none' */
@@ -16,6 +27,11 @@ export interface RetryPolicy {
     maxAttempts: number;
     /** Base backoff in milliseconds (used for 'exponential'). Default: 1000 */
     backoffMs: number;
+    /** Upper bound on a single backoff (used for 'exponential'). Default: 30000 */
+    maxDelayMs?: number;
+    /** Randomise each backoff between half and all of it. Default: true */
+    jitter?: boolean;
+    retryOn?: RetryOn;
 }
 
 export const DEFAULT_RETRY_POLICY: RetryPolicy = {
@@ -24,6 +40,16 @@ export const DEFAULT_RETRY_POLICY: RetryPolicy = {
     backoffMs: 1000,
 };
 
+/** One run of a node's command (CommandResult.attempts). */
+export interface Attempt {
+    attempt: number;
+    started_at: string;
+    duration_ms: number;
+    exit_code: number;
+    termination: Termination;
+    retry_delay_ms: number | null;
+}
+
 // ── Execution error ───────────────────────────────────────────────────────────
 
 /** Standardized error produced by the execution engine. */
@@ -42,6 +68,16 @@ export interface ExecutionError {
     timestamp: string;
 }
 
+// ── Termination ───────────────────────────────────────────────────────────────
+
+/** How a node's process ended (CommandResult.termination). */
+export type Termination =
+    | { kind: 'exited'; code: number }
+    | { kind: 'signaled'; signal: string; core_dumped: boolean }
+    | { kind: 'timed_out' }
+    | { kind: 'cancelled' }
+    | { kind: 'oom_killed' };
+
 // ── Dry run result ─────────────────────────────────────────────────────────────
 
 /** Result for a single node during a dry run simulation. */
@@ -62,7 +98,7 @@ export interface DryRunNodeResult {
 // ── Execution profile ─────────────────────────────────────────────────────────
 
 /** Which sandbox environment to execute a node inside. */
-export type ExecutionProfile = 'native' | 'docker' | 'ssh';
+export type ExecutionProfile = 'native' | 'docker' | 'ssh' | 'sandbox';
 
 /** Per-node execution profile stored in node.data.config.executionProfile */
 export interface NodeExecutionConfig {
@@ -71,14 +107,54 @@ export interface NodeExecutionConfig {
     timeoutSeconds: number;
     /** Docker image (only for profile='docker') */
     dockerImage?: string;
-    /** CPU limit, e.g. '0.5' (only for profile='docker') */
+    /** CPU limit in cores, e.g. '0.5' (docker, native and sandbox; native uses cgroups) */
     cpuLimit?: string;
-    /** Memory limit, e.g. '512m' (only for profile='docker') */
+    /** Memory limit, e.g. '512m' (docker, native and sandbox) */
     memLimit?: string;
+    /** Max processes/threads (only for profile='native' or 'sandbox') */
+    pidsLimit?: number;
+    /** Relative IO weight 1-10000 (only for profile='native' or 'sandbox') */
+    ioWeight?: number;
+    /** Env vars forwarded into the container: comma-separated names or PREFIX_* (only for profile='docker') */
+    dockerEnvAllowlist?: string;
+    /** Extra bind mounts, one `host:container[:ro]` per line (only for profile='docker') */
+    dockerVolumes?: string;
+    /** Network mode: bridge, host, none or a named network (only for profile='docker') */
+    dockerNetwork?: string;
+    /** Container user; 'host' maps to the host UID:GID (only for profile='docker') */
+    dockerUser?: string;
+    /** Image pull policy (only for profile='docker') */
+    dockerPull?: 'always' | 'missing' | 'never';
+    /** Entrypoint override (only for profile='docker') */
+    dockerEntrypoint?: string;
+    /** Shared memory size, e.g. '1g' (only for profile='docker') */
+    dockerShmSize?: string;
+    /** Container runtime; empty auto-detects Docker, then Podman (only for profile='docker') */
+    dockerRuntime?: '' | 'docker' | 'podman';
+    /** SELinux relabeling of bind mounts; empty uses ':Z' when SELinux is enforcing (only for profile='docker') */
+    dockerSelinuxLabel?: '' | 'private' | 'shared' | 'none';
+    /** Keep network access inside the sandbox (only for profile='sandbox') */
+    sandboxNetwork?: boolean;
+    /** Extra writable host paths, one per line (only for profile='sandbox') */
+    sandboxReadWrite?: string;
     /** SSH hostname (only for profile='ssh') */
     sshHost?: string;
     /** SSH username (only for profile='ssh') */
     sshUser?: string;
+    /** SSH port, default 22 (only for profile='ssh') */
+    sshPort?: number;
+    /** Path to a private key file (only for profile='ssh') */
+    sshIdentityFile?: string;
+    /** Keychain key holding a private key (only for profile='ssh') */
+    sshIdentitySecret?: string;
+    /** Bastion as [user@]host[:port] (only for profile='ssh') */
+    sshJumpHost?: string;
+    /** Working directory on the remote host (only for profile='ssh') */
+    sshRemoteCwd?: string;
+    /** Env vars forwarded to the remote: comma-separated names or PREFIX_* (only for profile='ssh') */
+    sshEnvAllowlist?: string;
+    /** Connection timeout in seconds (only for profile='ssh') */
+    sshConnectTimeout?: number;
 }
 
 export const DEFAULT_EXECUTION_CONFIG: NodeExecutionConfig = {
@@ -109,10 +109,10 @@ registerNode({
     headerBgClass: 'bg-purple-600/30',
     hoverClass: 'hover:bg-purple-900/20 hover:border-purple-600/40 hover:text-purple-400',
     executionHandler: 'scriptHandler',
-    defaultConfig: { command: 'echo Hello DevFlow!', shell: 'bash', workingDir: '' },
+    defaultConfig: { command: 'echo Hello DevFlow!', shell: 'auto', workingDir: '' },
     configSchema: [
         { key: 'command', label: 'Command', type: 'textarea', placeholder: 'echo Hello DevFlow!', mono: true },
-        { key: 'shell', label: 'Shell', type: 'select', options: ['auto', 'bash', 'sh', 'zsh', 'powershell'] },
+        { key: 'shell', label: 'Shell (name, path or args)', type: 'text', placeholder: 'auto · bash · /bin/zsh · bash -euo pipefail -c', mono: true },
         { key: 'workingDir', label: 'Working Dir', type: 'text', placeholder: '/path/to/dir', mono: true },
     ],
 });
@@ -250,7 +250,7 @@ registerNode({
     defaultConfig: { framework: 'jest', pattern: '', coverage: false },
     configSchema: [
         { key: 'framework', label: 'Framework', type: 'select', options: ['jest', 'pytest', 'go test', 'cargo test', 'mocha', 'vitest'] },
-        { key: 'pattern', label: 'Test Pattern', type: 'text', placeholder: 'src/**/*.test.ts', mono: true },
+        { key: 'pattern', label: 'Test Path or Glob', type: 'text', placeholder: 'src/**/*.test.ts', mono: true },
         { key: 'coverage', label: 'Generate Coverage', type: 'toggle' },
     ],
 });
@@ -2,7 +2,7 @@
 // DevFlow Studio — Shared types (UI-local copy)
 // ============================================================
 
-export type NodeStatus = 'idle' | 'running' | 'success' | 'error' | 'skipped';
+export type NodeStatus = 'idle' | 'paused' | 'running' | 'success' | 'success_with_warnings' | 'error' | 'skipped' | 'cancelled';
 export type NodeType = 'dockerBuild' | 'dockerRun' | 'gitPull' | 'scriptRun';
 
 export interface DockerBuildConfig { context: string; tag: string; dockerfile?: string; buildArgs?: Record<string, string>; }
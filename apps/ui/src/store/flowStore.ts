@@ -4,12 +4,13 @@
 // ============================================================
 
 import { create } from 'zustand';
+import { invoke } from '@tauri-apps/api/core';
 import { addEdge, applyNodeChanges, applyEdgeChanges } from 'reactflow';
 import type { Node, Edge, NodeChange, EdgeChange, Connection } from 'reactflow';
 import { getNodeDef } from '../lib/nodeRegistry.ts';
 import { saveFlowVersion as saveVersionToDb } from '../lib/versionRepository.ts';
 
-export type NodeStatus = 'idle' | 'running' | 'success' | 'error' | 'skipped';
+export type NodeStatus = 'idle' | 'paused' | 'running' | 'success' | 'success_with_warnings' | 'error' | 'skipped' | 'cancelled';
 export type DevFlowNodeType = string; // open string — registry-driven
 
 export interface DevFlowNodeData {
@@ -67,6 +68,10 @@ interface FlowStore {
 
     // Execution
     isRunning: boolean;
+    /** ID of the run in progress, for the Stop button */
+    currentRunId: string | null;
+    /** ID of the last finished run, which a resume carries results over from */
+    lastRunId: string | null;
     logs: LogEntry[];
 
     // Timeline (Phase 2)
@@ -102,6 +107,7 @@ interface FlowStore {
     removeNode: (nodeId: string) => void;
 
     setIsRunning: (running: boolean) => void;
+    setCurrentRunId: (runId: string | null) => void;
     addLog: (entry: Omit<LogEntry, 'id' | 'timestamp'>) => void;
     addNodeLogEntry: (nodeId: string, level: LogEntry['level'], message: string) => void;
     clearLogs: () => void;
@@ -142,6 +148,8 @@ export const useFlowStore = create<FlowStore>((set, get) => ({
     edges: [],
     selectedNodeId: null,
     isRunning: false,
+    currentRunId: null,
+    lastRunId: null,
     logs: [],
     executionTimeline: [],
     flowId: loadFlowIdFromStorage(),
@@ -212,6 +220,7 @@ export const useFlowStore = create<FlowStore>((set, get) => ({
     })),
 
     setIsRunning: (isRunning) => set({ isRunning }),
+    setCurrentRunId: (currentRunId) => set({ currentRunId }),
 
     addLog: (entry) => set(s => ({
         logs: [
@@ -295,9 +304,29 @@ export const useFlowStore = create<FlowStore>((set, get) => ({
     toggleAnalytics: () => set(s => ({ showAnalytics: !s.showAnalytics })),
 
     // ── Debugger Actions ────────────────────────────────────────────────────
-    toggleDebugMode: () => set(s => ({ isDebugMode: !s.isDebugMode, isPaused: false, currentDebugNodeId: null })),
-    resumeDebugStep: () => set({ isPaused: false }),
-    stopDebugging: () => set({ isDebugMode: false, isPaused: false, currentDebugNodeId: null }),
+    // The engine holds paused nodes (resume_node in flow_engine.rs); a run in
+    // progress picks up step mode being switched on or off.
+    toggleDebugMode: () => {
+        const { isDebugMode, currentRunId, currentDebugNodeId } = get();
+        if (currentRunId) {
+            invoke('resume_node', { runId: currentRunId, nodeId: isDebugMode ? currentDebugNodeId : null, step: !isDebugMode }).catch(() => {});
+        }
+        set({ isDebugMode: !isDebugMode, isPaused: false, currentDebugNodeId: null });
+    },
+    resumeDebugStep: () => {
+        const { currentRunId, currentDebugNodeId } = get();
+        if (currentRunId && currentDebugNodeId) {
+            invoke('resume_node', { runId: currentRunId, nodeId: currentDebugNodeId }).catch(() => {});
+        }
+        set({ isPaused: false });
+    },
+    stopDebugging: () => {
+        const { currentRunId, currentDebugNodeId } = get();
+        if (currentRunId) {
+            invoke('resume_node', { runId: currentRunId, nodeId: currentDebugNodeId, step: false }).catch(() => {});
+        }
+        set({ isDebugMode: false, isPaused: false, currentDebugNodeId: null });
+    },
 
     // ── Optimizer Actions ───────────────────────────────────────────────────
     toggleOptimizer: () => set(s => {
@@ -15,6 +15,8 @@ export interface ScanResult {
     hasRequirements: boolean;
     hasDockerCompose: boolean;
     packageScripts: string[];
+    /** Container runtime found on this machine, if any */
+    containerRuntime?: 'docker' | 'podman' | null;
 }
 
 interface ProjectStore {